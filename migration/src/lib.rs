@@ -8,6 +8,32 @@ mod m20230609_120307_add_guild_mod_role;
 mod m20230609_122133_alter_mod_role_optional;
 mod m20230611_042140_broaden_confessions_with_data;
 mod m20230701_075643_managed_subjects;
+mod m20261018_000001_add_confessions;
+mod m20261018_000002_encrypt_confession_authors;
+mod m20261018_000003_add_guild_pseudonyms;
+mod m20261018_000004_add_confession_replies;
+mod m20261018_000005_add_deny_reasons;
+mod m20261018_000006_add_vetting_quorum;
+mod m20261018_000007_add_confession_claims;
+mod m20261018_000008_add_confession_edits;
+mod m20261018_000009_add_pending_expiry;
+mod m20261018_000010_add_channel_vetting_mode;
+mod m20261018_000011_add_rate_limits;
+mod m20261018_000012_add_guild_blocks;
+mod m20261018_000013_add_filter_rules;
+mod m20261018_000014_add_confession_fingerprints;
+mod m20261018_000015_add_confession_image_data;
+mod m20261018_000016_add_confession_attachments;
+mod m20261018_000017_add_content_warnings;
+mod m20261018_000018_add_scheduled_posts;
+mod m20261018_000019_add_polls;
+mod m20261018_000020_add_ask_me;
+mod m20261018_000021_add_direct_confessions;
+mod m20261018_000022_add_modmail_threads;
+mod m20261018_000023_add_confession_subjects;
+mod m20261018_000024_add_reply_pseudonyms;
+mod m20261018_000025_add_direct_messages;
+mod m20261018_000026_add_reply_guild_author_hash;
 
 pub struct Migrator;
 
@@ -23,6 +49,32 @@ impl MigratorTrait for Migrator {
             Box::new(m20230609_122133_alter_mod_role_optional::Migration),
             Box::new(m20230611_042140_broaden_confessions_with_data::Migration),
             Box::new(m20230701_075643_managed_subjects::Migration),
+            Box::new(m20261018_000001_add_confessions::Migration),
+            Box::new(m20261018_000002_encrypt_confession_authors::Migration),
+            Box::new(m20261018_000003_add_guild_pseudonyms::Migration),
+            Box::new(m20261018_000004_add_confession_replies::Migration),
+            Box::new(m20261018_000005_add_deny_reasons::Migration),
+            Box::new(m20261018_000006_add_vetting_quorum::Migration),
+            Box::new(m20261018_000007_add_confession_claims::Migration),
+            Box::new(m20261018_000008_add_confession_edits::Migration),
+            Box::new(m20261018_000009_add_pending_expiry::Migration),
+            Box::new(m20261018_000010_add_channel_vetting_mode::Migration),
+            Box::new(m20261018_000011_add_rate_limits::Migration),
+            Box::new(m20261018_000012_add_guild_blocks::Migration),
+            Box::new(m20261018_000013_add_filter_rules::Migration),
+            Box::new(m20261018_000014_add_confession_fingerprints::Migration),
+            Box::new(m20261018_000015_add_confession_image_data::Migration),
+            Box::new(m20261018_000016_add_confession_attachments::Migration),
+            Box::new(m20261018_000017_add_content_warnings::Migration),
+            Box::new(m20261018_000018_add_scheduled_posts::Migration),
+            Box::new(m20261018_000019_add_polls::Migration),
+            Box::new(m20261018_000020_add_ask_me::Migration),
+            Box::new(m20261018_000021_add_direct_confessions::Migration),
+            Box::new(m20261018_000022_add_modmail_threads::Migration),
+            Box::new(m20261018_000023_add_confession_subjects::Migration),
+            Box::new(m20261018_000024_add_reply_pseudonyms::Migration),
+            Box::new(m20261018_000025_add_direct_messages::Migration),
+            Box::new(m20261018_000026_add_reply_guild_author_hash::Migration),
         ]
     }
 }
@@ -0,0 +1,98 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Confessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Confessions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Confessions::GuildId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Confessions::Number)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Confessions::ChannelId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Confessions::AuthorId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Confessions::Content).text().not_null())
+                    .col(ColumnDef::new(Confessions::Image).text())
+                    .col(ColumnDef::new(Confessions::Status).integer().not_null())
+                    .col(ColumnDef::new(Confessions::VettingMessageId).big_unsigned())
+                    .col(ColumnDef::new(Confessions::PostedMessageId).big_unsigned())
+                    .col(
+                        ColumnDef::new(Confessions::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Confessions::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-confessions-guild-number")
+                    .table(Confessions::Table)
+                    .col(Confessions::GuildId)
+                    .col(Confessions::Number)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Confessions::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    Id,
+    GuildId,
+    Number,
+    ChannelId,
+    AuthorId,
+    Content,
+    Image,
+    Status,
+    VettingMessageId,
+    PostedMessageId,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(
+                        ColumnDef::new(Confessions::AuthorRef)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .drop_column(Confessions::AuthorId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(
+                        ColumnDef::new(Confessions::AuthorId)
+                            .big_unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .drop_column(Confessions::AuthorRef)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    AuthorId,
+    AuthorRef,
+}
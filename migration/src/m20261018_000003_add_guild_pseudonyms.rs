@@ -0,0 +1,121 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildPseudonyms::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuildPseudonyms::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildPseudonyms::GuildId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildPseudonyms::Epoch)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildPseudonyms::AuthorHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildPseudonyms::Pseudonym)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-guild-pseudonyms-pseudonym")
+                    .table(GuildPseudonyms::Table)
+                    .col(GuildPseudonyms::GuildId)
+                    .col(GuildPseudonyms::Epoch)
+                    .col(GuildPseudonyms::Pseudonym)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-guild-pseudonyms-author")
+                    .table(GuildPseudonyms::Table)
+                    .col(GuildPseudonyms::GuildId)
+                    .col(GuildPseudonyms::Epoch)
+                    .col(GuildPseudonyms::AuthorHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(
+                        ColumnDef::new(Confessions::Pseudonym)
+                            .unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildPseudonyms::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .drop_column(Confessions::Pseudonym)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum GuildPseudonyms {
+    Table,
+    Id,
+    GuildId,
+    Epoch,
+    AuthorHash,
+    Pseudonym,
+}
+
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    Pseudonym,
+}
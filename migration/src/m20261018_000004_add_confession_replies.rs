@@ -0,0 +1,107 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConfessionReplies::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConfessionReplies::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionReplies::ConfessionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionReplies::AuthorHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionReplies::Pseudonym)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ConfessionReplies::Content).text().not_null())
+                    .col(
+                        ColumnDef::new(ConfessionReplies::Status)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ConfessionReplies::VettingMessageId).big_unsigned())
+                    .col(ColumnDef::new(ConfessionReplies::PostedMessageId).big_unsigned())
+                    .col(
+                        ColumnDef::new(ConfessionReplies::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .add_column(
+                        ColumnDef::new(Guild::VetReplies)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ConfessionReplies::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .drop_column(Guild::VetReplies)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ConfessionReplies {
+    Table,
+    Id,
+    ConfessionId,
+    AuthorHash,
+    Pseudonym,
+    Content,
+    Status,
+    VettingMessageId,
+    PostedMessageId,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Guild {
+    Table,
+    VetReplies,
+}
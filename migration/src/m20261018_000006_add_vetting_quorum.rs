@@ -0,0 +1,109 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConfessionVotes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConfessionVotes::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionVotes::ConfessionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionVotes::ModeratorId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionVotes::Approve)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-confession-votes-moderator")
+                    .table(ConfessionVotes::Table)
+                    .col(ConfessionVotes::ConfessionId)
+                    .col(ConfessionVotes::ModeratorId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .add_column(
+                        ColumnDef::new(Guild::RequiredApprovals)
+                            .unsigned()
+                            .not_null()
+                            .default(1),
+                    )
+                    .add_column(
+                        ColumnDef::new(Guild::RequiredDenials)
+                            .unsigned()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ConfessionVotes::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .drop_column(Guild::RequiredApprovals)
+                    .drop_column(Guild::RequiredDenials)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ConfessionVotes {
+    Table,
+    Id,
+    ConfessionId,
+    ModeratorId,
+    Approve,
+}
+
+#[derive(Iden)]
+enum Guild {
+    Table,
+    RequiredApprovals,
+    RequiredDenials,
+}
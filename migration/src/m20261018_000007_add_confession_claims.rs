@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(ColumnDef::new(Confessions::ClaimedBy).big_unsigned())
+                    .add_column(ColumnDef::new(Confessions::ClaimedUntil).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .drop_column(Confessions::ClaimedBy)
+                    .drop_column(Confessions::ClaimedUntil)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    ClaimedBy,
+    ClaimedUntil,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(ColumnDef::new(Confessions::EditedContent).text())
+                    .add_column(ColumnDef::new(Confessions::EditedBy).big_unsigned())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .drop_column(Confessions::EditedContent)
+                    .drop_column(Confessions::EditedBy)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    EditedContent,
+    EditedBy,
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channels::Table)
+                    .add_column(
+                        ColumnDef::new(Channels::VettingMode)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .add_column(ColumnDef::new(Guild::TrustedRole).big_unsigned())
+                    .add_column(
+                        ColumnDef::new(Guild::TrustedAfter)
+                            .unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(
+                        ColumnDef::new(Confessions::AuthorHash)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channels::Table)
+                    .drop_column(Channels::VettingMode)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .drop_column(Guild::TrustedRole)
+                    .drop_column(Guild::TrustedAfter)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .drop_column(Confessions::AuthorHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Channels {
+    Table,
+    VettingMode,
+}
+
+#[derive(Iden)]
+enum Guild {
+    Table,
+    TrustedRole,
+    TrustedAfter,
+}
+
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    AuthorHash,
+}
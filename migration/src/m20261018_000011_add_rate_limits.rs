@@ -0,0 +1,98 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildRateLimits::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuildRateLimits::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildRateLimits::GuildId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GuildRateLimits::ChannelId).big_unsigned())
+                    .col(
+                        ColumnDef::new(GuildRateLimits::MaxConfessions)
+                            .unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(GuildRateLimits::PeriodMinutes)
+                            .unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(GuildRateLimits::MaxPending)
+                            .unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-confessions-author-hash")
+                    .table(Confessions::Table)
+                    .col(Confessions::GuildId)
+                    .col(Confessions::AuthorHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-confessions-author-hash")
+                    .table(Confessions::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(GuildRateLimits::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum GuildRateLimits {
+    Table,
+    Id,
+    GuildId,
+    ChannelId,
+    MaxConfessions,
+    PeriodMinutes,
+    MaxPending,
+}
+
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    GuildId,
+    AuthorHash,
+}
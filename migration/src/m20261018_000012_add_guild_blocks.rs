@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildBlocks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuildBlocks::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildBlocks::GuildId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GuildBlocks::AuthorHash).string().not_null())
+                    .col(
+                        ColumnDef::new(GuildBlocks::ConfessionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildBlocks::BlockedBy)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GuildBlocks::ExpiresAt).timestamp())
+                    .col(
+                        ColumnDef::new(GuildBlocks::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-guild-blocks-author")
+                    .table(GuildBlocks::Table)
+                    .col(GuildBlocks::GuildId)
+                    .col(GuildBlocks::AuthorHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildBlocks::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum GuildBlocks {
+    Table,
+    Id,
+    GuildId,
+    AuthorHash,
+    ConfessionId,
+    BlockedBy,
+    ExpiresAt,
+    CreatedAt,
+}
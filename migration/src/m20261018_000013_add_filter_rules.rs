@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildFilterRules::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuildFilterRules::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildFilterRules::GuildId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GuildFilterRules::Kind).integer().not_null())
+                    .col(ColumnDef::new(GuildFilterRules::Pattern).string())
+                    .col(
+                        ColumnDef::new(GuildFilterRules::Action)
+                            .integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildFilterRules::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum GuildFilterRules {
+    Table,
+    Id,
+    GuildId,
+    Kind,
+    Pattern,
+    Action,
+}
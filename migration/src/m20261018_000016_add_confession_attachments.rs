@@ -0,0 +1,198 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConfessionAttachments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConfessionAttachments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionAttachments::ConfessionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionAttachments::Position)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionAttachments::Filename)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionAttachments::ContentType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionAttachments::Data)
+                            .blob(BlobSize::Long)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionAttachments::Spoiler)
+                            .tiny_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-confession-attachments-confession")
+                    .table(ConfessionAttachments::Table)
+                    .col(ConfessionAttachments::ConfessionId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Pending confessions may still hold an image from before attachments had their own table.
+        let move_images = Query::insert()
+            .into_table(ConfessionAttachments::Table)
+            .columns([
+                ConfessionAttachments::ConfessionId,
+                ConfessionAttachments::Filename,
+                ConfessionAttachments::ContentType,
+                ConfessionAttachments::Data,
+            ])
+            .select_from(
+                Query::select()
+                    .column(Confessions::Id)
+                    .expr(Expr::val("image.png"))
+                    .expr(Expr::val("image/png"))
+                    .column(Confessions::ImageData)
+                    .from(Confessions::Table)
+                    .and_where(Expr::col(Confessions::ImageData).is_not_null())
+                    .to_owned(),
+            )
+            .map_err(|e| DbErr::Custom(e.to_string()))?
+            .to_owned();
+        manager.exec_stmt(move_images).await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .drop_column(Confessions::ImageData)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .add_column(
+                        ColumnDef::new(Guild::MaxAttachments)
+                            .unsigned()
+                            .not_null()
+                            .default(1),
+                    )
+                    .add_column(
+                        ColumnDef::new(Guild::MaxAttachmentMb)
+                            .unsigned()
+                            .not_null()
+                            .default(8),
+                    )
+                    .add_column(
+                        ColumnDef::new(Guild::AllowVideo)
+                            .tiny_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(
+                        ColumnDef::new(Guild::AllowAudio)
+                            .tiny_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(ColumnDef::new(Guild::AllowedFileTypes).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guild::Table)
+                    .drop_column(Guild::MaxAttachments)
+                    .drop_column(Guild::MaxAttachmentMb)
+                    .drop_column(Guild::AllowVideo)
+                    .drop_column(Guild::AllowAudio)
+                    .drop_column(Guild::AllowedFileTypes)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(
+                        ColumnDef::new(Confessions::ImageData)
+                            .blob(BlobSize::Long)
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ConfessionAttachments::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ConfessionAttachments {
+    Table,
+    Id,
+    ConfessionId,
+    Position,
+    Filename,
+    ContentType,
+    Data,
+    Spoiler,
+}
+
+#[derive(Iden)]
+enum Confessions {
+    Table,
+    Id,
+    ImageData,
+}
+
+#[derive(Iden)]
+enum Guild {
+    Table,
+    MaxAttachments,
+    MaxAttachmentMb,
+    AllowVideo,
+    AllowAudio,
+    AllowedFileTypes,
+}
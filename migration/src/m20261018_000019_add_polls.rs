@@ -0,0 +1,110 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConfessionPolls::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConfessionPolls::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConfessionPolls::ConfessionId)
+                            .integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ConfessionPolls::Options).text().not_null())
+                    .col(
+                        ColumnDef::new(ConfessionPolls::DurationHours)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ConfessionPolls::ClosesAt).timestamp())
+                    .col(
+                        ColumnDef::new(ConfessionPolls::Closed)
+                            .tiny_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PollVotes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PollVotes::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PollVotes::PollId).integer().not_null())
+                    .col(ColumnDef::new(PollVotes::VoterHash).string().not_null())
+                    .col(ColumnDef::new(PollVotes::OptionIndex).unsigned().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-poll-votes-voter")
+                    .table(PollVotes::Table)
+                    .col(PollVotes::PollId)
+                    .col(PollVotes::VoterHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PollVotes::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ConfessionPolls::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ConfessionPolls {
+    Table,
+    Id,
+    ConfessionId,
+    Options,
+    DurationHours,
+    ClosesAt,
+    Closed,
+}
+
+#[derive(Iden)]
+enum PollVotes {
+    Table,
+    Id,
+    PollId,
+    VoterHash,
+    OptionIndex,
+}
@@ -0,0 +1,146 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DirectConfessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DirectConfessions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DirectConfessions::GuildId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DirectConfessions::AuthorRef)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DirectConfessions::AuthorHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DirectConfessions::RecipientId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DirectConfessions::Content).text().not_null())
+                    .col(
+                        ColumnDef::new(DirectConfessions::Status)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DirectConfessions::VettingMessageId).big_unsigned())
+                    .col(
+                        ColumnDef::new(DirectConfessions::Blocked)
+                            .tiny_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(DirectConfessions::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MemberSettings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MemberSettings::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MemberSettings::GuildId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MemberSettings::UserId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MemberSettings::AcceptDirect)
+                            .tiny_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-member-settings-member")
+                    .table(MemberSettings::Table)
+                    .col(MemberSettings::GuildId)
+                    .col(MemberSettings::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MemberSettings::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(DirectConfessions::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum DirectConfessions {
+    Table,
+    Id,
+    GuildId,
+    AuthorRef,
+    AuthorHash,
+    RecipientId,
+    Content,
+    Status,
+    VettingMessageId,
+    Blocked,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum MemberSettings {
+    Table,
+    Id,
+    GuildId,
+    UserId,
+    AcceptDirect,
+}
@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReplyPseudonyms::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ReplyPseudonyms::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ReplyPseudonyms::ConfessionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ReplyPseudonyms::AuthorHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ReplyPseudonyms::Pseudonym)
+                            .string()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-reply-pseudonyms-pseudonym")
+                    .table(ReplyPseudonyms::Table)
+                    .col(ReplyPseudonyms::ConfessionId)
+                    .col(ReplyPseudonyms::Pseudonym)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-reply-pseudonyms-author")
+                    .table(ReplyPseudonyms::Table)
+                    .col(ReplyPseudonyms::ConfessionId)
+                    .col(ReplyPseudonyms::AuthorHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReplyPseudonyms::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ReplyPseudonyms {
+    Table,
+    Id,
+    ConfessionId,
+    AuthorHash,
+    Pseudonym,
+}
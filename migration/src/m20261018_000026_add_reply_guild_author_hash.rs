@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConfessionReplies::Table)
+                    .add_column(
+                        ColumnDef::new(ConfessionReplies::GuildAuthorHash)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-confession-replies-guild-author-hash")
+                    .table(ConfessionReplies::Table)
+                    .col(ConfessionReplies::GuildAuthorHash)
+                    .col(ConfessionReplies::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-confession-replies-guild-author-hash")
+                    .table(ConfessionReplies::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConfessionReplies::Table)
+                    .drop_column(ConfessionReplies::GuildAuthorHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ConfessionReplies {
+    Table,
+    GuildAuthorHash,
+    CreatedAt,
+}
@@ -37,8 +37,9 @@ pub async fn send_unauthorised_message(ctx: &Context<'_>, required: Auth) -> Res
     }
 }
 
-pub async fn respond_based_on_auth_context(ctx: &Context<'_>, required: Auth) -> Result<bool> {
-    let result = match required {
+// Checks without replying, for places like autocomplete that can't send a message.
+pub async fn is_authorised(ctx: &Context<'_>, required: Auth) -> Result<bool> {
+    match required {
         Auth::Everyone => Ok(true),
         Auth::Admin => match ctx.partial_guild().await {
             Some(_) => {
@@ -72,8 +73,11 @@ pub async fn respond_based_on_auth_context(ctx: &Context<'_>, required: Auth) ->
                 .unwrap_or(false)),
             None => Ok(false),
         },
-    };
-    match result {
+    }
+}
+
+pub async fn respond_based_on_auth_context(ctx: &Context<'_>, required: Auth) -> Result<bool> {
+    match is_authorised(ctx, required).await {
         Err(e) => {
             send_unauthorised_message(ctx, required).await?;
             println!("Error: {}", e.to_string());
@@ -1,16 +1,33 @@
-use poise::serenity_prelude as serenity;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ConfessionButton {
-    ApproveConfession((serenity::UserId, serenity::ChannelId)),
-    DenyConfession,
+    ApproveConfession(i32),
+    DenyConfession(i32),
+    DenyWithReason(i32),
+    ClaimConfession(i32),
+    EditConfession(i32),
+    BlockAuthor(i32),
+    BlockDuration(i32),
+    ContentWarning(i32),
+    PollVote(i32, u32),
+    AnswerQuestion(i32),
+    SkipQuestion(i32),
+    PickQuestion,
+    ApproveDirect(i32),
+    DenyDirect(i32),
+    DirectReply(i32),
+    DirectBlock(i32),
+    ModmailReply(i32),
+    ReplyConfession(i32),
+    ApproveReply(i32),
+    DenyReply(i32),
     None,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ConfessionRevealButton {
-    RevealConfession(String),
+    RevealConfession(u64),
     KeepConfession,
     None,
 }
@@ -0,0 +1,64 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::entity::confessions;
+
+// Autocomplete fires on every keystroke, so lookups are kept for a short while per guild.
+const AUTOCOMPLETE_TTL: Duration = Duration::from_secs(60);
+
+pub struct TimedCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K: Eq + Hash, V: Clone> TimedCache<K, V> {
+    pub fn new(ttl: Duration) -> Self {
+        TimedCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+            entries.insert(key, (Instant::now(), value));
+        }
+    }
+
+    pub fn invalidate(&self, key: &K) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(key);
+        }
+    }
+}
+
+pub struct AutocompleteCache {
+    pub subjects: TimedCache<u64, Vec<String>>,
+    pub user_subjects: TimedCache<(u64, u64), Vec<String>>,
+    pub confession_channels: TimedCache<u64, Vec<u64>>,
+    pub recent_confessions: TimedCache<u64, Vec<confessions::Model>>,
+}
+
+impl AutocompleteCache {
+    pub fn new() -> Self {
+        AutocompleteCache {
+            subjects: TimedCache::new(AUTOCOMPLETE_TTL),
+            user_subjects: TimedCache::new(AUTOCOMPLETE_TTL),
+            confession_channels: TimedCache::new(AUTOCOMPLETE_TTL),
+            recent_confessions: TimedCache::new(AUTOCOMPLETE_TTL),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use poise::{serenity_prelude as serenity, AutocompleteChoice};
+
+use crate::{
+    auth,
+    entity::confessions,
+    operations::{self, channels::ChannelUse},
+    Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+// Discord only shows this many suggestions.
+const MAX_SUGGESTIONS: usize = 25;
+
+fn matches(candidate: &str, partial: &str) -> bool {
+    candidate
+        .to_lowercase()
+        .starts_with(&partial.to_lowercase())
+}
+
+async fn guild_subjects(ctx: &Context<'_>, guild_id: u64) -> Vec<String> {
+    let cache = &ctx.data().autocomplete.subjects;
+    if let Some(subjects) = cache.get(&guild_id) {
+        return subjects;
+    }
+    match operations::subjects::get_guild_subjects(&ctx.data().database, guild_id).await {
+        Ok(subjects) => {
+            cache.insert(guild_id, subjects.clone());
+            subjects
+        }
+        Err(why) => {
+            println!("Error getting subjects: {:?}", why);
+            vec![]
+        }
+    }
+}
+
+async fn user_subjects(ctx: &Context<'_>, guild_id: u64, user_id: u64) -> Vec<String> {
+    let cache = &ctx.data().autocomplete.user_subjects;
+    if let Some(subjects) = cache.get(&(guild_id, user_id)) {
+        return subjects;
+    }
+    match operations::subjects::get_user_subjects(&ctx.data().database, guild_id, user_id).await {
+        Ok(subjects) => {
+            cache.insert((guild_id, user_id), subjects.clone());
+            subjects
+        }
+        Err(why) => {
+            println!("Error getting subjects: {:?}", why);
+            vec![]
+        }
+    }
+}
+
+// Completes the last word of a space delimited list, keeping the words already typed.
+fn complete_list(subjects: Vec<String>, partial: &str) -> Vec<String> {
+    let (typed, last) = match partial.rsplit_once(' ') {
+        Some((typed, last)) => (Some(typed), last),
+        None => (None, partial),
+    };
+    let already = typed
+        .map(|typed| typed.split(' ').collect::<Vec<&str>>())
+        .unwrap_or_default();
+    subjects
+        .into_iter()
+        .filter(|subject| matches(subject, last) && !already.contains(&subject.as_str()))
+        .map(|subject| match typed {
+            Some(typed) => format!("{} {}", typed, subject),
+            None => subject,
+        })
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+pub async fn subject(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let subjects = match ctx.guild_id() {
+        Some(guild_id) => guild_subjects(&ctx, guild_id.0).await,
+        None => vec![],
+    };
+    subjects
+        .into_iter()
+        .filter(|subject| matches(subject, partial))
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+pub async fn subject_list(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let subjects = match ctx.guild_id() {
+        Some(guild_id) => guild_subjects(&ctx, guild_id.0).await,
+        None => vec![],
+    };
+    complete_list(subjects, partial)
+}
+
+// Only the caller's own subjects are suggested, since they're the ones usually being removed.
+pub async fn own_subject_list(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let subjects = match ctx.guild_id() {
+        Some(guild_id) => user_subjects(&ctx, guild_id.0, ctx.author().id.0).await,
+        None => vec![],
+    };
+    complete_list(subjects, partial)
+}
+
+pub async fn confession_channel(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice<String>> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.0,
+        None => return vec![],
+    };
+    let cache = &ctx.data().autocomplete.confession_channels;
+    let channel_ids = match cache.get(&guild_id) {
+        Some(channel_ids) => channel_ids,
+        None => match operations::channels::get_channels_in_guild_with_use(
+            &ctx.data().database,
+            guild_id,
+            ChannelUse::Confession,
+        )
+        .await
+        {
+            Ok(channels) => {
+                let channel_ids = channels
+                    .into_iter()
+                    .map(|channel_model| channel_model.id)
+                    .collect::<Vec<u64>>();
+                cache.insert(guild_id, channel_ids.clone());
+                channel_ids
+            }
+            Err(why) => {
+                println!("Error getting channels: {:?}", why);
+                vec![]
+            }
+        },
+    };
+    let mut choices = vec![];
+    for channel_id in channel_ids {
+        let name = serenity::ChannelId(channel_id)
+            .name(ctx)
+            .await
+            .unwrap_or(channel_id.to_string());
+        if matches(&name, partial.trim_start_matches('#')) {
+            choices.push(AutocompleteChoice {
+                name: format!("#{}", name),
+                value: channel_id.to_string(),
+            });
+        }
+    }
+    choices.truncate(MAX_SUGGESTIONS);
+    choices
+}
+
+fn confession_label(confession: &confessions::Model) -> String {
+    let mut label = format!(
+        "#{} [{:x}] {}",
+        confession.number, confession.pseudonym, confession.content
+    );
+    // Choice names are capped at 100 characters.
+    if label.chars().count() > 100 {
+        label = label.chars().take(99).collect::<String>() + "…";
+    }
+    label
+}
+
+// Moderators can look up recent confessions by number or pseudonym, everyone else gets nothing.
+pub async fn moderated_confession(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice<u64>> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.0,
+        None => return vec![],
+    };
+    if !auth::is_authorised(&ctx, auth::Auth::Admin)
+        .await
+        .unwrap_or(false)
+    {
+        return vec![];
+    }
+    let cache = &ctx.data().autocomplete.recent_confessions;
+    let recent = match cache.get(&guild_id) {
+        Some(recent) => recent,
+        None => match operations::confessions::get_recent_confessions(
+            &ctx.data().database,
+            guild_id,
+            MAX_SUGGESTIONS as u64,
+        )
+        .await
+        {
+            Ok(recent) => {
+                cache.insert(guild_id, recent.clone());
+                recent
+            }
+            Err(why) => {
+                println!("Error getting confessions: {:?}", why);
+                vec![]
+            }
+        },
+    };
+    let partial = partial.trim_start_matches(['#', '[']);
+    recent
+        .iter()
+        .filter(|confession| {
+            confession.number.to_string().starts_with(partial)
+                || matches(&format!("{:x}", confession.pseudonym), partial)
+        })
+        .map(|confession| AutocompleteChoice {
+            name: confession_label(confession),
+            value: confession.number,
+        })
+        .collect()
+}
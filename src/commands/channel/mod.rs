@@ -6,7 +6,7 @@ use crate::{auth, operations, Data};
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
 
-use super::super::operations::channels::ChannelUse;
+use super::super::operations::channels::{ChannelUse, VettingMode};
 
 pub async fn set_channel(ctx: &Context<'_>, channel_use: ChannelUse) -> Result<(), Error> {
     let channel_result = operations::channels::add_channel_for_guild(
@@ -16,6 +16,10 @@ pub async fn set_channel(ctx: &Context<'_>, channel_use: ChannelUse) -> Result<(
         channel_use,
     )
     .await;
+    ctx.data()
+        .autocomplete
+        .confession_channels
+        .invalidate(&ctx.guild_id().unwrap().0);
     let response = match channel_result {
         Ok(_) => format!("Set channel usage to {}.", channel_use),
         Err(e) => e.to_string(),
@@ -55,3 +59,66 @@ pub async fn set_none(ctx: Context<'_>) -> Result<(), Error> {
     };
     super::channel::set_channel(&ctx, ChannelUse::None).await
 }
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_channel_vetting(
+    ctx: Context<'_>,
+    #[description = "When confessions to this channel are vetted"] mode: VettingMode,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let set_result = operations::channels::set_channel_vetting_mode(
+        &ctx.data().database,
+        ctx.guild_id().unwrap().0,
+        ctx.channel_id().0,
+        mode,
+    )
+    .await;
+    let response = match set_result {
+        Ok(_) => format!("Set channel vetting to {}.", mode),
+        Err(e) => e.to_string(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        warn!("Error sending message: {:?}", why_discord_say);
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_channel_content_warning(
+    ctx: Context<'_>,
+    #[description = "Whether confessions to this channel need a content warning"] required: bool,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let set_result = operations::channels::set_channel_requires_content_warning(
+        &ctx.data().database,
+        ctx.guild_id().unwrap().0,
+        ctx.channel_id().0,
+        required,
+    )
+    .await;
+    let response = match set_result {
+        Ok(_) if required => "Confessions to this channel now need a content warning.".to_owned(),
+        Ok(_) => "Confessions to this channel no longer need a content warning.".to_owned(),
+        Err(e) => e.to_string(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        warn!("Error sending message: {:?}", why_discord_say);
+    }
+    Ok(())
+}
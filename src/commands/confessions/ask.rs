@@ -0,0 +1,513 @@
+use anyhow::anyhow;
+use poise::{serenity_prelude as serenity, Modal};
+
+use super::{replies, vetting, warnings};
+use crate::{
+    auth, button,
+    entity::confessions,
+    operations::{self, channels::ChannelUse},
+    util, Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+const MAX_LISTED_QUESTIONS: usize = 25;
+const QUESTION_PREVIEW_LENGTH: usize = 80;
+
+#[derive(Debug, Modal)]
+#[name = "Answer"]
+struct AnswerModal {
+    #[name = "Your answer"]
+    #[min_length = 1]
+    #[max_length = 1000]
+    #[paragraph]
+    answer: String,
+}
+
+fn question_content(confession: &confessions::Model) -> String {
+    let content = confession
+        .edited_content
+        .as_ref()
+        .unwrap_or(&confession.content);
+    match &confession.content_warning {
+        Some(content_warning) => warnings::hide_behind_warning(content_warning, content),
+        None => content.clone(),
+    }
+}
+
+fn question_embed(confession: &confessions::Model) -> serenity::CreateEmbed {
+    let mut embed = serenity::CreateEmbed::default();
+    embed
+        .title(format!("Question #{}", confession.number))
+        .author(|a| a.name(format!("[{:x}]", confession.pseudonym)))
+        .colour(confession.pseudonym)
+        .description(question_content(confession));
+    embed
+}
+
+fn host_embed(confession: &confessions::Model) -> serenity::CreateEmbed {
+    let mut embed = question_embed(confession);
+    embed.field("Channel", format!("<#{}>", confession.channel_id), true);
+    embed
+}
+
+fn add_answer_buttons(
+    components: &mut serenity::CreateComponents,
+    confession_id: i32,
+) -> &mut serenity::CreateComponents {
+    components.create_action_row(|action_row| {
+        action_row
+            .create_button(|b| {
+                b.label("Answer")
+                    .style(serenity::ButtonStyle::Success)
+                    .custom_id(button::ConfessionButton::AnswerQuestion(confession_id).to_string())
+            })
+            .create_button(|b| {
+                b.label("Skip")
+                    .style(serenity::ButtonStyle::Secondary)
+                    .custom_id(button::ConfessionButton::SkipQuestion(confession_id).to_string())
+            })
+    })
+}
+
+pub async fn is_ask_me(data: &Data, confession: &confessions::Model) -> anyhow::Result<bool> {
+    let channel_use = operations::channels::get_channel_use(
+        &data.database,
+        confession.guild_id,
+        confession.channel_id,
+    )
+    .await?;
+    Ok(channel_use == ChannelUse::AskMe)
+}
+
+// Approved questions go to the host instead of the channel. They stay listed in `/questions` if the DM can't be sent.
+pub async fn deliver_question(
+    ctx: &serenity::Context,
+    data: &Data,
+    confession: &confessions::Model,
+) -> anyhow::Result<()> {
+    // The host can only answer or skip approved questions, anything else would be stuck in their DMs.
+    if operations::confessions::ConfessionStatus::from(confession.status)
+        != operations::confessions::ConfessionStatus::Approved
+    {
+        return Err(anyhow!(
+            "Question #{} has not been approved.",
+            confession.number
+        ));
+    }
+    let host_id = operations::channels::get_channel_host(
+        &data.database,
+        confession.guild_id,
+        confession.channel_id,
+    )
+    .await?
+    .ok_or(anyhow!("<#{}> has no host.", confession.channel_id))?;
+    let dm_channel = serenity::UserId(host_id).create_dm_channel(ctx).await;
+    let sent = match dm_channel {
+        Ok(dm_channel) => dm_channel
+            .send_message(ctx, |m| {
+                m.content("You have a new question. Use `/questions` to see everything waiting.")
+                    .set_embed(host_embed(confession))
+                    .components(|components| add_answer_buttons(components, confession.id))
+            })
+            .await
+            .map(|_| ()),
+        Err(why) => Err(why),
+    };
+    if let Err(why) = sent {
+        println!("Error sending message: {:?}", why);
+    }
+    Ok(())
+}
+
+// Returns the confession only if the person pressing the button hosts its channel.
+async fn get_hosted_question(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) -> Option<confessions::Model> {
+    let confession =
+        match operations::confessions::get_confession(&data.database, confession_id).await {
+            Ok(Some(confession)) => confession,
+            Ok(None) => {
+                vetting::respond_ephemeral(ctx, component, "Question not found".to_owned()).await;
+                return None;
+            }
+            Err(why) => {
+                println!("Error getting confession: {:?}", why);
+                return None;
+            }
+        };
+    let host_id = operations::channels::get_channel_host(
+        &data.database,
+        confession.guild_id,
+        confession.channel_id,
+    )
+    .await
+    .unwrap_or_default();
+    if host_id != Some(component.user.id.0) {
+        vetting::respond_ephemeral(
+            ctx,
+            component,
+            "Only the host of this channel can do that.".to_owned(),
+        )
+        .await;
+        return None;
+    }
+    if confession.answer.is_some() || confession.posted_message_id.is_some() {
+        vetting::respond_ephemeral(
+            ctx,
+            component,
+            "This question has already been answered.".to_owned(),
+        )
+        .await;
+        return None;
+    }
+    if operations::confessions::ConfessionStatus::from(confession.status)
+        != operations::confessions::ConfessionStatus::Approved
+    {
+        vetting::respond_ephemeral(ctx, component, vetting::conflict_message(&confession)).await;
+        return None;
+    }
+    Some(confession)
+}
+
+async fn post_answer(
+    ctx: &serenity::Context,
+    data: &Data,
+    mut confession: confessions::Model,
+    host: &serenity::User,
+    answer: &str,
+) -> anyhow::Result<confessions::Model> {
+    let posted = serenity::ChannelId(confession.channel_id)
+        .send_message(ctx, |m| {
+            let mut embed = question_embed(&confession);
+            embed.field(format!("Answer from {}", host.name), answer, false);
+            m.set_embed(embed).components(|components| {
+                components.create_action_row(|action_row| {
+                    action_row.add_button(replies::create_reply_button(confession.id))
+                })
+            })
+        })
+        .await?;
+    confession.posted_message_id = Some(posted.id.0);
+    operations::confessions::set_confession(&data.database, confession).await
+}
+
+pub async fn answer_question(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let confession = match get_hosted_question(ctx, component, data, confession_id).await {
+        Some(confession) => confession,
+        None => return,
+    };
+    let modal = util::execute_component_modal::<AnswerModal>(ctx, component, None, None).await;
+    let (modal_interaction, answer_modal) = match modal {
+        Ok(Some(submitted)) => submitted,
+        Ok(None) => return,
+        Err(why) => {
+            println!("Error getting modal: {:?}", why);
+            return;
+        }
+    };
+    let answer = answer_modal.answer.trim().to_owned();
+    let posted = match operations::confessions::answer_question(
+        &data.database,
+        confession.id,
+        component.user.id.0,
+        answer.clone(),
+    )
+    .await
+    {
+        Ok(true) => {
+            let posted = post_answer(ctx, data, confession.clone(), &component.user, &answer).await;
+            if posted.is_err() {
+                if let Err(why) =
+                    operations::confessions::clear_answer(&data.database, confession.id).await
+                {
+                    println!("Error clearing answer: {:?}", why);
+                }
+            }
+            posted
+        }
+        Ok(false) => Err(anyhow!("This question has already been answered.")),
+        Err(e) => Err(e),
+    };
+    let response = match posted {
+        Ok(confession) => {
+            modal_interaction
+                .create_interaction_response(&ctx.http, |response_builder| {
+                    response_builder
+                        .kind(serenity::InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|response_data| {
+                            response_data
+                                .content(format!(
+                                    "Answered question #{} in <#{}>.",
+                                    confession.number, confession.channel_id
+                                ))
+                                .components(|components| components)
+                        })
+                })
+                .await
+        }
+        Err(e) => {
+            modal_interaction
+                .create_interaction_response(&ctx.http, |response_builder| {
+                    response_builder.interaction_response_data(|response_data| {
+                        response_data
+                            .content(format!("Error answering question: {}", e))
+                            .ephemeral(true)
+                    })
+                })
+                .await
+        }
+    };
+    if let Err(why) = response {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+pub async fn skip_question(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let confession = match get_hosted_question(ctx, component, data, confession_id).await {
+        Some(confession) => confession,
+        None => return,
+    };
+    match operations::confessions::skip_question(&data.database, confession.id, component.user.id.0)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            vetting::respond_ephemeral(
+                ctx,
+                component,
+                "This question has already been answered.".to_owned(),
+            )
+            .await;
+            return;
+        }
+        Err(e) => {
+            vetting::respond_ephemeral(ctx, component, format!("Error skipping question: {}", e))
+                .await;
+            return;
+        }
+    }
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content(format!("Skipped question #{}.", confession.number))
+                        .components(|components| components)
+                })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+pub async fn pick_question(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+) {
+    let confession_id = match component
+        .data
+        .values
+        .first()
+        .and_then(|value| value.parse::<i32>().ok())
+    {
+        Some(confession_id) => confession_id,
+        None => return,
+    };
+    let confession = match get_hosted_question(ctx, component, data, confession_id).await {
+        Some(confession) => confession,
+        None => return,
+    };
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data
+                    .set_embed(host_embed(&confession))
+                    .components(|components| add_answer_buttons(components, confession.id))
+                    .ephemeral(true)
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+fn preview(confession: &confessions::Model) -> String {
+    let content = confession
+        .edited_content
+        .as_ref()
+        .unwrap_or(&confession.content);
+    match (&confession.content_warning, content.chars().count()) {
+        (Some(content_warning), _) => format!("CW: {}", content_warning),
+        (None, length) if length > QUESTION_PREVIEW_LENGTH => format!(
+            "{}…",
+            content
+                .chars()
+                .take(QUESTION_PREVIEW_LENGTH - 1)
+                .collect::<String>()
+        ),
+        (None, _) => content.clone(),
+    }
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    description_localized("en-GB", "Lists the questions waiting for your answer."),
+    description_localized("en-US", "Lists the questions waiting for your answer."),
+    guild_only = true
+)]
+pub async fn questions(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap().0;
+    let hosted = operations::channels::get_hosted_channels(
+        &ctx.data().database,
+        guild_id,
+        ctx.author().id.0,
+    )
+    .await;
+    let questions = match hosted {
+        Ok(channels) if channels.is_empty() => {
+            Err(anyhow!("You aren't the host of any channel here."))
+        }
+        Ok(channels) => {
+            operations::confessions::get_unanswered_questions(
+                &ctx.data().database,
+                guild_id,
+                channels.iter().map(|channel| channel.id).collect(),
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    let questions = match questions {
+        Ok(questions) if questions.is_empty() => {
+            ctx.send(|builder| {
+                builder
+                    .content("There are no questions waiting for an answer.")
+                    .ephemeral(true)
+            })
+            .await?;
+            return Ok(());
+        }
+        Ok(questions) => questions,
+        Err(e) => {
+            ctx.send(|builder| builder.content(e.to_string()).ephemeral(true))
+                .await?;
+            return Ok(());
+        }
+    };
+    let listed = &questions[..questions.len().min(MAX_LISTED_QUESTIONS)];
+    let mut content = listed
+        .iter()
+        .map(|question| {
+            format!(
+                "**#{}** in <#{}>: {}",
+                question.number,
+                question.channel_id,
+                preview(question)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    if questions.len() > listed.len() {
+        content.push_str(&format!("\n…and {} more", questions.len() - listed.len()));
+    }
+    ctx.send(|builder| {
+        builder
+            .content(content)
+            .components(|components| {
+                components.create_action_row(|action_row| {
+                    action_row.create_select_menu(|menu| {
+                        menu.custom_id(button::ConfessionButton::PickQuestion.to_string())
+                            .placeholder("Pick a question to answer or skip")
+                            .options(|options| {
+                                for question in listed {
+                                    options.create_option(|option| {
+                                        option
+                                            .label(format!("Question #{}", question.number))
+                                            .value(question.id)
+                                    });
+                                }
+                                options
+                            })
+                    })
+                })
+            })
+            .ephemeral(true)
+    })
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_ask_me(
+    ctx: Context<'_>,
+    #[description = "Who answers the questions asked in this channel"] host: serenity::UserId,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let guild_id = ctx.guild_id().unwrap().0;
+    let set_result = match operations::channels::add_channel_for_guild(
+        &ctx.data().database,
+        guild_id,
+        ctx.channel_id().0,
+        ChannelUse::AskMe,
+    )
+    .await
+    {
+        Ok(_) => {
+            operations::channels::set_channel_host(
+                &ctx.data().database,
+                guild_id,
+                ctx.channel_id().0,
+                host.0,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    ctx.data()
+        .autocomplete
+        .confession_channels
+        .invalidate(&guild_id);
+    let response = match set_result {
+        Ok(_) => format!(
+            "Set channel usage to {}. Questions asked here go to <@{}>.",
+            ChannelUse::AskMe,
+            host
+        ),
+        Err(e) => e.to_string(),
+    };
+    ctx.send(|builder| {
+        builder
+            .content(response)
+            .allowed_mentions(|mentions| mentions.empty_parse())
+    })
+    .await?;
+    Ok(())
+}
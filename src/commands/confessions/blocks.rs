@@ -0,0 +1,229 @@
+use anyhow::anyhow;
+use poise::serenity_prelude as serenity;
+
+use crate::{auth, button, crypto, operations, Data};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+// (label, days), where 0 days blocks permanently.
+const BLOCK_DURATIONS: [(&str, i64); 4] = [
+    ("1 day", 1),
+    ("7 days", 7),
+    ("30 days", 30),
+    ("Permanently", 0),
+];
+
+fn describe_expiry(expires_at: Option<chrono::DateTime<chrono::Utc>>) -> String {
+    match expires_at {
+        Some(expires_at) => format!("until <t:{}:f>", expires_at.timestamp()),
+        None => "permanently".to_owned(),
+    }
+}
+
+// Returns a message for the confessor if they're blocked from confessing in this guild.
+pub async fn check_blocked(
+    data: &Data,
+    guild_id: u64,
+    author_hash: &str,
+) -> anyhow::Result<Option<String>> {
+    let block = operations::blocks::get_active_block(&data.database, guild_id, author_hash).await?;
+    Ok(block.map(|block| {
+        format!(
+            "You are blocked from confessing in this server {}.",
+            describe_expiry(block.expires_at)
+        )
+    }))
+}
+
+pub async fn open_block_menu(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    confession_id: i32,
+) {
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data
+                    .content("How long should the author of this confession be blocked for?")
+                    .ephemeral(true)
+                    .components(|components| {
+                        components.create_action_row(|action_row| {
+                            action_row.create_select_menu(|menu| {
+                                menu.custom_id(
+                                    button::ConfessionButton::BlockDuration(confession_id)
+                                        .to_string(),
+                                )
+                                .placeholder("Pick a duration")
+                                .options(|options| {
+                                    for (label, days) in BLOCK_DURATIONS {
+                                        options.create_option(|option| {
+                                            option.label(label).value(days)
+                                        });
+                                    }
+                                    options
+                                })
+                            })
+                        })
+                    })
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+async fn block_author(
+    data: &Data,
+    moderator: serenity::UserId,
+    confession_id: i32,
+    days: i64,
+) -> anyhow::Result<String> {
+    let confession =
+        match operations::confessions::get_confession(&data.database, confession_id).await? {
+            Some(confession) => confession,
+            None => return Err(anyhow!("Confession not found")),
+        };
+    // Worked out from the encrypted reference so confessions from before author hashes were stored can be blocked too.
+    let author_id = crypto::decrypt_author(&data.author_key, &confession.author_ref)?;
+    let author_hash = crypto::hash_author(&data.author_key, confession.guild_id, author_id);
+    let expires_at = match days {
+        0 => None,
+        days => Some(chrono::Utc::now() + chrono::Duration::days(days)),
+    };
+    operations::blocks::set_block(
+        &data.database,
+        confession.guild_id,
+        author_hash,
+        confession.id,
+        moderator.0,
+        expires_at,
+    )
+    .await?;
+    Ok(format!(
+        "Blocked the author of confession #{} {}.",
+        confession.number,
+        describe_expiry(expires_at)
+    ))
+}
+
+pub async fn select_block_duration(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let selected = component.data.values.first().cloned().unwrap_or_default();
+    let response = match selected.parse::<i64>() {
+        Ok(days) => block_author(data, component.user.id, confession_id, days).await,
+        Err(_) => Err(anyhow!("Unknown duration: {}", selected)),
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => format!("Error blocking author: {}", e),
+    };
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response_builder| {
+            response_builder
+                .kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content(response)
+                        .components(|components| components)
+                })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn get_blocks(ctx: Context<'_>) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let blocks = operations::blocks::get_guild_blocks(&db, this_guild).await?;
+    if blocks.is_empty() {
+        ctx.say("Nobody is blocked from confessing.").await?;
+        return Ok(());
+    }
+    let mut lines = vec![];
+    for block in blocks {
+        let number = operations::confessions::get_confession(&db, block.confession_id)
+            .await?
+            .map(|confession| format!("#{}", confession.number))
+            .unwrap_or("unknown".to_owned());
+        lines.push(format!(
+            "- Author of confession {}, blocked by <@{}> {}",
+            number,
+            block.blocked_by,
+            describe_expiry(block.expires_at)
+        ));
+    }
+    ctx.send(|builder| {
+        builder
+            .content(format!("Blocked confessors:\n{}", lines.join("\n")))
+            .allowed_mentions(|mentions| mentions.empty_parse())
+    })
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn unblock(
+    ctx: Context<'_>,
+    #[description = "Confession number the block was made from"] confession: u64,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let unblocked = match operations::confessions::get_confession_by_number(
+        &db, this_guild, confession,
+    )
+    .await
+    {
+        Ok(Some(found)) => {
+            match crypto::decrypt_author(&ctx.data().author_key, &found.author_ref) {
+                Ok(author_id) => {
+                    let author_hash =
+                        crypto::hash_author(&ctx.data().author_key, this_guild, author_id);
+                    operations::blocks::remove_block(&db, this_guild, &author_hash).await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Ok(None) => Err(anyhow!("Could not find confession #{}", confession)),
+        Err(e) => Err(e),
+    };
+    match unblocked {
+        Ok(_) => {
+            ctx.say(format!(
+                "Unblocked the author of confession #{}.",
+                confession
+            ))
+            .await?
+        }
+        Err(why) => ctx.say(format!("Error unblocking: {}", why)).await?,
+    };
+    Ok(())
+}
@@ -0,0 +1,343 @@
+use anyhow::anyhow;
+use poise::{serenity_prelude as serenity, Modal};
+
+use crate::{
+    auth, button,
+    operations::{self, confessions::ConfessionStatus},
+    util, Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+pub const CUSTOM_REASON: &str = "custom";
+pub const NO_REASON: &str = "none";
+// Select menus hold 25 options; two are taken by the custom and no reason options.
+const MAX_PRESET_REASONS: usize = 23;
+const MAX_OPTION_LABEL: usize = 100;
+
+#[derive(Debug, Modal)]
+#[name = "Deny confession"]
+struct DenyModal {
+    #[name = "Reason (sent to the confessor)"]
+    #[max_length = 500]
+    #[paragraph]
+    reason: Option<String>,
+}
+
+pub async fn finish_deny(
+    ctx: &serenity::Context,
+    data: &Data,
+    vetting_channel: serenity::ChannelId,
+    moderator: serenity::UserId,
+    confession_id: i32,
+    reason: Option<String>,
+) -> anyhow::Result<String> {
+    let mut confession =
+        super::vetting::take_confession(data, moderator, confession_id, ConfessionStatus::Denied)
+            .await?;
+    confession.deny_reason = reason.clone();
+    let confession = operations::confessions::set_confession(&data.database, confession).await?;
+    if let Err(why) =
+        operations::attachments::remove_confession_attachments(&data.database, confession.id).await
+    {
+        println!("Error removing attachments: {:?}", why);
+    }
+
+    if let Some(vetting_message_id) = confession.vetting_message_id {
+        let vetting_message_id = serenity::MessageId(vetting_message_id);
+        super::clear_components(ctx, vetting_channel, vetting_message_id).await;
+        if let Err(why) = vetting_channel
+            .send_message(ctx, |m| {
+                m.content(format!(
+                    "Confession #{} denied by <@{}>{}",
+                    confession.number,
+                    moderator,
+                    reason
+                        .as_ref()
+                        .map(|r| format!("\nReason: {}", r))
+                        .unwrap_or("".to_owned())
+                ))
+                .reference_message((vetting_channel, vetting_message_id))
+                .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+            .await
+        {
+            println!("Error sending message: {:?}", why);
+        }
+    }
+
+    match super::notify_author(
+        ctx,
+        data,
+        &confession,
+        "Your confession was denied".to_owned(),
+        reason,
+    )
+    .await
+    {
+        Ok(_) => Ok(format!("Confession #{} denied.", confession.number)),
+        Err(why) => {
+            println!("Error notifying author: {:?}", why);
+            Ok(format!(
+                "Confession #{} denied. The confessor could not be messaged.",
+                confession.number
+            ))
+        }
+    }
+}
+
+async fn open_deny_modal(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let modal = util::execute_component_modal::<DenyModal>(ctx, component, None, None).await;
+    let (modal_interaction, deny_modal) = match modal {
+        Ok(Some(submitted)) => submitted,
+        Ok(None) => return,
+        Err(why) => {
+            println!("Error getting modal: {:?}", why);
+            return;
+        }
+    };
+    let response = match finish_deny(
+        ctx,
+        data,
+        component.channel_id,
+        component.user.id,
+        confession_id,
+        deny_modal.reason,
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(e) => format!("Error denying confession: {}", e),
+    };
+    if let Err(why) = modal_interaction
+        .create_interaction_response(&ctx.http, |response_builder| {
+            response_builder.interaction_response_data(|response_data| {
+                response_data.content(response).ephemeral(true)
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+pub async fn open_deny(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let reasons = match component.guild_id {
+        Some(guild_id) => {
+            operations::deny_reasons::get_guild_deny_reasons_raw(&data.database, guild_id.0)
+                .await
+                .unwrap_or_default()
+        }
+        None => vec![],
+    };
+    if reasons.is_empty() {
+        open_deny_modal(ctx, component, data, confession_id).await;
+        return;
+    }
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data
+                    .content("Why is this confession being denied?")
+                    .ephemeral(true)
+                    .components(|components| {
+                        components.create_action_row(|action_row| {
+                            action_row.create_select_menu(|menu| {
+                                menu.custom_id(
+                                    button::ConfessionButton::DenyWithReason(confession_id)
+                                        .to_string(),
+                                )
+                                .placeholder("Pick a reason")
+                                .options(|options| {
+                                    for reason in reasons.iter().take(MAX_PRESET_REASONS) {
+                                        options.create_option(|option| {
+                                            option
+                                                .label(
+                                                    reason
+                                                        .reason
+                                                        .chars()
+                                                        .take(MAX_OPTION_LABEL)
+                                                        .collect::<String>(),
+                                                )
+                                                .value(reason.id)
+                                        });
+                                    }
+                                    options
+                                        .create_option(|option| {
+                                            option.label("Other reason…").value(CUSTOM_REASON)
+                                        })
+                                        .create_option(|option| {
+                                            option.label("No reason").value(NO_REASON)
+                                        })
+                                })
+                            })
+                        })
+                    })
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+pub async fn select_deny_reason(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let selected = component.data.values.first().cloned().unwrap_or_default();
+    let reason = match selected.as_str() {
+        CUSTOM_REASON => {
+            open_deny_modal(ctx, component, data, confession_id).await;
+            return;
+        }
+        NO_REASON => Ok(None),
+        reason_id => match (reason_id.parse::<i32>(), component.guild_id) {
+            (Ok(reason_id), Some(guild_id)) => {
+                match operations::deny_reasons::get_deny_reason(
+                    &data.database,
+                    guild_id.0,
+                    reason_id,
+                )
+                .await
+                {
+                    Ok(Some(found)) => Ok(Some(found.reason)),
+                    // Denying without the reason the moderator picked would mislead the confessor.
+                    Ok(None) => Err(anyhow!(
+                        "That reason has been removed. Press Deny again to pick another."
+                    )),
+                    Err(e) => Err(e),
+                }
+            }
+            _ => Err(anyhow!("Unknown reason: {}", reason_id)),
+        },
+    };
+    let response = match reason {
+        Ok(reason) => match finish_deny(
+            ctx,
+            data,
+            component.channel_id,
+            component.user.id,
+            confession_id,
+            reason,
+        )
+        .await
+        {
+            Ok(response) => response,
+            Err(e) => format!("Error denying confession: {}", e),
+        },
+        Err(e) => format!("Error denying confession: {}", e),
+    };
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response_builder| {
+            response_builder
+                .kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content(response)
+                        .components(|components| components)
+                })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn add_deny_reason(
+    ctx: Context<'_>,
+    #[description = "Reason"] reason: String,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    if let Err(why) =
+        operations::deny_reasons::add_guild_deny_reason(&db, this_guild, reason.clone()).await
+    {
+        ctx.say(format!("Error adding deny reason: {}", why))
+            .await?;
+    } else {
+        ctx.say(format!("Added deny reason: {}", reason)).await?;
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn get_deny_reasons(ctx: Context<'_>) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let reasons = operations::deny_reasons::get_guild_deny_reasons(&db, this_guild).await?;
+    let reasons_string = reasons
+        .into_iter()
+        .map(|r| format!("- {}", r))
+        .collect::<Vec<String>>()
+        .join("\n");
+    ctx.send(|builder| {
+        builder
+            .content(format!("Deny reasons:\n{}", reasons_string))
+            .ephemeral(true)
+    })
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn remove_deny_reason(
+    ctx: Context<'_>,
+    #[description = "Reason"] reason: String,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    if let Err(why) =
+        operations::deny_reasons::remove_guild_deny_reason(&db, this_guild, reason.clone()).await
+    {
+        ctx.say(format!("Error removing deny reason: {}", why))
+            .await?;
+    } else {
+        ctx.say(format!("Removed deny reason: {}", reason)).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,582 @@
+use anyhow::anyhow;
+use poise::{serenity_prelude as serenity, Modal};
+
+use super::{blocks, limits, vetting};
+use crate::{
+    button, crypto,
+    entity::direct_confessions,
+    filter,
+    operations::{self, channels::ChannelUse, confessions::ConfessionStatus},
+    util, Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+#[derive(Debug, Modal)]
+#[name = "Reply"]
+struct DirectReplyModal {
+    #[name = "Message"]
+    #[min_length = 1]
+    #[max_length = 1000]
+    #[paragraph]
+    content: String,
+}
+
+fn not_accepting(recipient: &serenity::User) -> String {
+    format!("{} isn't accepting direct confessions.", recipient.name)
+}
+
+fn server_name(ctx: &serenity::Context, direct_confession: &direct_confessions::Model) -> String {
+    serenity::GuildId(direct_confession.guild_id)
+        .name(ctx)
+        .unwrap_or("a server".to_owned())
+}
+
+// The recipient can block the sender from their copy, the sender only ever gets to reply.
+async fn send_direct(
+    ctx: &serenity::Context,
+    direct_confession: &direct_confessions::Model,
+    to: serenity::UserId,
+    title: String,
+    content: &str,
+) -> anyhow::Result<()> {
+    let to_recipient = to.0 == direct_confession.recipient_id;
+    let dm_channel = to.create_dm_channel(ctx).await?;
+    dm_channel
+        .send_message(ctx, |m| {
+            m.embed(|embed| {
+                embed
+                    .title(title)
+                    .description(content)
+                    .footer(|f| f.text(format!("Via {}", server_name(ctx, direct_confession))))
+            })
+            .components(|components| {
+                components.create_action_row(|action_row| {
+                    action_row.create_button(|b| {
+                        b.label("Reply")
+                            .style(serenity::ButtonStyle::Primary)
+                            .custom_id(
+                                button::ConfessionButton::DirectReply(direct_confession.id)
+                                    .to_string(),
+                            )
+                    });
+                    if to_recipient {
+                        action_row.create_button(|b| {
+                            b.label("Block sender")
+                                .style(serenity::ButtonStyle::Danger)
+                                .custom_id(
+                                    button::ConfessionButton::DirectBlock(direct_confession.id)
+                                        .to_string(),
+                                )
+                        });
+                    }
+                    action_row
+                })
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+async fn send_verify_direct(
+    ctx: &Context<'_>,
+    recipient: &serenity::User,
+    content: String,
+    flags: Vec<String>,
+) -> anyhow::Result<()> {
+    let guild_id = ctx.guild_id().unwrap().0;
+    let vetting_channels = operations::channels::get_channels_in_guild_with_use(
+        &ctx.data().database,
+        guild_id,
+        ChannelUse::Vetting,
+    )
+    .await?;
+    let channel_id = match vetting_channels.first() {
+        Some(channel_model) => serenity::ChannelId(channel_model.id),
+        None => return Err(anyhow!("There is no vetting channel set.")),
+    };
+    let direct_confession = operations::direct_confessions::add_direct_confession(
+        &ctx.data().database,
+        guild_id,
+        crypto::encrypt_author(&ctx.data().author_key, ctx.author().id.0)?,
+        crypto::hash_author(&ctx.data().author_key, guild_id, ctx.author().id.0),
+        recipient.id.0,
+        content,
+    )
+    .await?;
+    let sent = channel_id
+        .send_message(ctx, |m| {
+            m.embed(|embed| {
+                embed
+                    .title(format!("Direct confession #{}", direct_confession.id))
+                    .description(&direct_confession.content);
+                if !flags.is_empty() {
+                    embed.field("Flags", flags.join("\n"), false);
+                }
+                embed
+            })
+            .content(format!("Direct confession going to <@{}>", recipient.id))
+            .allowed_mentions(|mentions| mentions.empty_parse())
+            .components(|components| {
+                components.create_action_row(|action_row| {
+                    action_row
+                        .create_button(|b| {
+                            b.label("Approve")
+                                .style(serenity::ButtonStyle::Success)
+                                .custom_id(
+                                    button::ConfessionButton::ApproveDirect(direct_confession.id)
+                                        .to_string(),
+                                )
+                        })
+                        .create_button(|b| {
+                            b.label("Deny")
+                                .style(serenity::ButtonStyle::Danger)
+                                .custom_id(
+                                    button::ConfessionButton::DenyDirect(direct_confession.id)
+                                        .to_string(),
+                                )
+                        })
+                })
+            })
+        })
+        .await?;
+    operations::direct_confessions::set_direct_vetting_message(
+        &ctx.data().database,
+        direct_confession.id,
+        sent.id.0,
+    )
+    .await
+}
+
+async fn confess_direct(
+    ctx: &Context<'_>,
+    recipient: &serenity::User,
+    content: String,
+) -> anyhow::Result<String> {
+    if recipient.bot || recipient.id == ctx.author().id {
+        return Err(anyhow!(not_accepting(recipient)));
+    }
+    let guild_id = ctx.guild_id().unwrap().0;
+    let author_hash = crypto::hash_author(&ctx.data().author_key, guild_id, ctx.author().id.0);
+    let refused = match blocks::check_blocked(ctx.data(), guild_id, &author_hash).await? {
+        None => limits::check_direct_rate_limits(ctx.data(), guild_id, &author_hash).await?,
+        blocked => blocked,
+    };
+    if let Some(refusal) = refused {
+        return Err(anyhow!(refusal));
+    }
+    // A block looks the same as opting out, so the sender can't tell they were blocked.
+    let accepts = operations::member_settings::get_accepts_direct(
+        &ctx.data().database,
+        guild_id,
+        recipient.id.0,
+    )
+    .await?;
+    let blocked = operations::direct_confessions::is_direct_sender_blocked(
+        &ctx.data().database,
+        guild_id,
+        &author_hash,
+        recipient.id.0,
+    )
+    .await?;
+    if !accepts || blocked {
+        return Err(anyhow!(not_accepting(recipient)));
+    }
+    let rules = operations::filters::get_guild_filters(&ctx.data().database, guild_id).await?;
+    let filtered = filter::apply_filters(&rules, &content);
+    if let Some(rejection) = filtered.rejection {
+        return Ok(format!(
+            "Your direct confession was rejected because {}.",
+            rejection
+        ));
+    }
+    send_verify_direct(ctx, recipient, filtered.content, filtered.flags).await?;
+    Ok("Your direct confession has been sent to be vetted.".to_owned())
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    description_localized("en-GB", "Anonymously confesses to a member in their DMs."),
+    description_localized("en-US", "Anonymously confesses to a member in their DMs."),
+    guild_only = true
+)]
+pub async fn confess_to_user(
+    ctx: Context<'_>,
+    #[description = "Member to confess to"] user: serenity::User,
+    #[description = "Content"]
+    #[max_length = 1000]
+    content: String,
+) -> Result<(), Error> {
+    let response = match confess_direct(&ctx, &user, content).await {
+        Ok(response) => response,
+        Err(e) => format!("{}\nYour direct confession has not been processed.", e),
+    };
+    ctx.send(|builder| builder.content(response).ephemeral(true).reply(true))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn allow_direct_confessions(
+    ctx: Context<'_>,
+    #[description = "Whether members can confess to you directly"] allowed: bool,
+) -> Result<(), Error> {
+    let set_result = operations::member_settings::set_accepts_direct(
+        &ctx.data().database,
+        ctx.guild_id().unwrap().0,
+        ctx.author().id.0,
+        allowed,
+    )
+    .await;
+    let response = match set_result {
+        Ok(_) if allowed => "Members of this server can now confess to you directly.".to_owned(),
+        Ok(_) => "Members of this server can no longer confess to you directly.".to_owned(),
+        Err(e) => e.to_string(),
+    };
+    ctx.send(|builder| builder.content(response).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+async fn resolve_direct(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    direct_confession_id: i32,
+    status: ConfessionStatus,
+) -> Option<direct_confessions::Model> {
+    let resolved = operations::direct_confessions::resolve_direct_confession(
+        &data.database,
+        direct_confession_id,
+        status,
+    )
+    .await;
+    let direct_confession =
+        operations::direct_confessions::get_direct_confession(&data.database, direct_confession_id)
+            .await;
+    match (resolved, direct_confession) {
+        (Ok(true), Ok(Some(direct_confession))) => Some(direct_confession),
+        (Ok(false), Ok(Some(direct_confession))) => {
+            vetting::respond_ephemeral(
+                ctx,
+                component,
+                format!(
+                    "This direct confession has already been {}.",
+                    ConfessionStatus::from(direct_confession.status)
+                ),
+            )
+            .await;
+            None
+        }
+        (_, Ok(None)) => {
+            vetting::respond_ephemeral(ctx, component, "Direct confession not found".to_owned())
+                .await;
+            None
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            vetting::respond_ephemeral(
+                ctx,
+                component,
+                format!("Error resolving direct confession: {}", e),
+            )
+            .await;
+            None
+        }
+    }
+}
+
+async fn respond_resolved(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    content: String,
+) -> bool {
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data
+                    .content(content)
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+        return false;
+    }
+    true
+}
+
+pub async fn approve_direct(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    direct_confession_id: i32,
+) -> bool {
+    let direct_confession = match resolve_direct(
+        ctx,
+        component,
+        data,
+        direct_confession_id,
+        ConfessionStatus::Approved,
+    )
+    .await
+    {
+        Some(direct_confession) => direct_confession,
+        None => return false,
+    };
+    let delivered = send_direct(
+        ctx,
+        &direct_confession,
+        serenity::UserId(direct_confession.recipient_id),
+        "Anonymous message".to_owned(),
+        &direct_confession.content,
+    )
+    .await;
+    let content = match delivered {
+        Ok(_) => format!("Direct confession accepted by <@{}>", component.user.id),
+        Err(why) => {
+            println!("Error sending direct confession: {:?}", why);
+            format!(
+                "Direct confession accepted by <@{}>, but it could not be delivered.",
+                component.user.id
+            )
+        }
+    };
+    respond_resolved(ctx, component, content).await
+}
+
+pub async fn deny_direct(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    direct_confession_id: i32,
+) -> bool {
+    let direct_confession = match resolve_direct(
+        ctx,
+        component,
+        data,
+        direct_confession_id,
+        ConfessionStatus::Denied,
+    )
+    .await
+    {
+        Some(direct_confession) => direct_confession,
+        None => return false,
+    };
+    let notified = match crypto::decrypt_author(&data.author_key, &direct_confession.author_ref) {
+        Ok(author_id) => match serenity::UserId(author_id).create_dm_channel(ctx).await {
+            Ok(dm_channel) => dm_channel
+                .send_message(ctx, |m| {
+                    m.embed(|embed| {
+                        embed
+                            .title("Direct confession not delivered")
+                            .description(&direct_confession.content)
+                            .footer(|f| {
+                                f.text(format!("Via {}", server_name(ctx, &direct_confession)))
+                            })
+                    })
+                })
+                .await
+                .map(|_| ())
+                .map_err(|e| anyhow!(e)),
+            Err(e) => Err(anyhow!(e)),
+        },
+        Err(e) => Err(e),
+    };
+    if let Err(why) = notified {
+        println!("Error notifying author: {:?}", why);
+    }
+    respond_resolved(
+        ctx,
+        component,
+        format!("Direct confession denied by <@{}>", component.user.id),
+    )
+    .await
+}
+
+// Works out who the other side of the conversation is without revealing the sender to the recipient.
+fn other_side(
+    data: &Data,
+    direct_confession: &direct_confessions::Model,
+    user: serenity::UserId,
+) -> anyhow::Result<serenity::UserId> {
+    if user.0 == direct_confession.recipient_id {
+        return Ok(serenity::UserId(crypto::decrypt_author(
+            &data.author_key,
+            &direct_confession.author_ref,
+        )?));
+    }
+    if crypto::hash_author(&data.author_key, direct_confession.guild_id, user.0)
+        == direct_confession.author_hash
+    {
+        return Ok(serenity::UserId(direct_confession.recipient_id));
+    }
+    Err(anyhow!("You aren't part of this conversation."))
+}
+
+async fn relay_direct(
+    ctx: &serenity::Context,
+    data: &Data,
+    direct_confession: &direct_confessions::Model,
+    user: &serenity::User,
+    content: String,
+) -> anyhow::Result<String> {
+    if direct_confession.blocked == 1 {
+        return Err(anyhow!("This conversation has been closed."));
+    }
+    let to = other_side(data, direct_confession, user.id)?;
+    if to.0 == direct_confession.recipient_id {
+        // The sender is still held to the server's rules after the first message.
+        let guild_id = direct_confession.guild_id;
+        let author_hash = &direct_confession.author_hash;
+        let refused = match blocks::check_blocked(data, guild_id, author_hash).await? {
+            None => limits::check_direct_rate_limits(data, guild_id, author_hash).await?,
+            blocked => blocked,
+        };
+        if let Some(refusal) = refused {
+            return Err(anyhow!(refusal));
+        }
+        let rules =
+            operations::filters::get_guild_filters(&data.database, direct_confession.guild_id)
+                .await?;
+        let filtered = filter::apply_filters(&rules, &content);
+        if let Some(rejection) = filtered.rejection {
+            return Err(anyhow!("Your message was rejected because {}.", rejection));
+        }
+        send_direct(
+            ctx,
+            direct_confession,
+            to,
+            "Anonymous message".to_owned(),
+            &filtered.content,
+        )
+        .await?;
+        if let Err(why) =
+            operations::direct_confessions::add_direct_message(&data.database, direct_confession)
+                .await
+        {
+            println!("Error saving direct message: {:?}", why);
+        }
+    } else {
+        send_direct(
+            ctx,
+            direct_confession,
+            to,
+            format!("Reply from {}", user.name),
+            &content,
+        )
+        .await?;
+    }
+    Ok("Your message has been sent.".to_owned())
+}
+
+pub async fn open_direct_reply_modal(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    direct_confession_id: i32,
+) {
+    let direct_confession = match operations::direct_confessions::get_direct_confession(
+        &data.database,
+        direct_confession_id,
+    )
+    .await
+    {
+        Ok(Some(direct_confession))
+            if ConfessionStatus::from(direct_confession.status) == ConfessionStatus::Approved =>
+        {
+            direct_confession
+        }
+        Ok(_) => {
+            vetting::respond_ephemeral(
+                ctx,
+                component,
+                "This message can't be replied to.".to_owned(),
+            )
+            .await;
+            return;
+        }
+        Err(why) => {
+            println!("Error getting direct confession: {:?}", why);
+            return;
+        }
+    };
+    let modal = util::execute_component_modal::<DirectReplyModal>(ctx, component, None, None).await;
+    let (modal_interaction, reply_modal) = match modal {
+        Ok(Some(submitted)) => submitted,
+        Ok(None) => return,
+        Err(why) => {
+            println!("Error getting modal: {:?}", why);
+            return;
+        }
+    };
+    let response = match relay_direct(
+        ctx,
+        data,
+        &direct_confession,
+        &component.user,
+        reply_modal.content,
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(e) => format!(
+            "Error sending message: {}\nYour message has not been sent.",
+            e
+        ),
+    };
+    if let Err(why) = modal_interaction
+        .create_interaction_response(&ctx.http, |response_builder| {
+            response_builder.interaction_response_data(|response_data| {
+                response_data.content(response).ephemeral(true)
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+pub async fn block_direct_sender(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    direct_confession_id: i32,
+) {
+    let blocked = match operations::direct_confessions::get_direct_confession(
+        &data.database,
+        direct_confession_id,
+    )
+    .await
+    {
+        Ok(Some(direct_confession)) if direct_confession.recipient_id == component.user.id.0 => {
+            operations::direct_confessions::block_direct_sender(
+                &data.database,
+                direct_confession.id,
+            )
+            .await
+        }
+        Ok(_) => Err(anyhow!("Only the recipient can block the sender.")),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = blocked {
+        vetting::respond_ephemeral(ctx, component, format!("Error blocking sender: {}", e)).await;
+        return;
+    }
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content("You blocked this sender. They can't message you through the bot any more.")
+                        .components(|components| components)
+                })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
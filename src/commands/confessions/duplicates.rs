@@ -0,0 +1,59 @@
+use crate::{
+    entity::confessions,
+    fingerprint,
+    operations::{self, channels::ChannelUse, guild::DuplicateAction},
+    Data,
+};
+
+const DUPLICATE_WINDOW_DAYS: i64 = 14;
+
+async fn confession_link(data: &Data, confession: &confessions::Model) -> String {
+    let message = match confession.posted_message_id {
+        Some(posted_message_id) => Some((confession.channel_id, posted_message_id)),
+        None => {
+            let vetting_channel = operations::channels::get_channels_in_guild_with_use(
+                &data.database,
+                confession.guild_id,
+                ChannelUse::Vetting,
+            )
+            .await
+            .ok()
+            .and_then(|channels| channels.first().map(|channel| channel.id));
+            vetting_channel.zip(confession.vetting_message_id)
+        }
+    };
+    match message {
+        Some((channel_id, message_id)) => format!(
+            "[confession #{}](https://discord.com/channels/{}/{}/{})",
+            confession.number, confession.guild_id, channel_id, message_id
+        ),
+        None => format!("confession #{}", confession.number),
+    }
+}
+
+// The guild's configured action and a link to the earlier confession, if this one is a near-duplicate.
+pub async fn find_duplicate(
+    data: &Data,
+    guild_id: u64,
+    content: &str,
+) -> anyhow::Result<Option<(DuplicateAction, String)>> {
+    let action = operations::guild::get_guild_duplicate_action(&data.database, guild_id).await?;
+    let fingerprint = fingerprint::simhash(content);
+    if action == DuplicateAction::Ignore || fingerprint == 0 {
+        return Ok(None);
+    }
+    let since = chrono::Utc::now() - chrono::Duration::days(DUPLICATE_WINDOW_DAYS);
+    let recent = operations::confessions::get_fingerprinted_confessions_since(
+        &data.database,
+        guild_id,
+        since,
+    )
+    .await?;
+    match recent.iter().find(|confession| {
+        fingerprint::distance(confession.fingerprint, fingerprint)
+            <= fingerprint::MAX_DUPLICATE_DISTANCE
+    }) {
+        Some(duplicate) => Ok(Some((action, confession_link(data, duplicate).await))),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::anyhow;
+use poise::{serenity_prelude as serenity, Modal};
+
+use super::vetting::{self, Approval};
+use crate::{
+    operations::{self, confessions::ConfessionStatus},
+    util, Data,
+};
+
+#[derive(Debug, Modal)]
+#[name = "Edit confession"]
+struct EditModal {
+    #[name = "Confession content"]
+    #[min_length = 1]
+    #[max_length = 4000]
+    #[paragraph]
+    content: String,
+}
+
+async fn submit_edit(
+    ctx: &serenity::Context,
+    data: &Data,
+    moderator: serenity::UserId,
+    confession_id: i32,
+    content: String,
+) -> anyhow::Result<Approval> {
+    let edited = operations::confessions::edit_confession(
+        &data.database,
+        confession_id,
+        moderator.0,
+        content,
+    )
+    .await?;
+    if !edited {
+        return match operations::confessions::get_confession(&data.database, confession_id).await? {
+            Some(confession) => Err(anyhow!(vetting::conflict_message(&confession))),
+            None => Err(anyhow!("Confession not found")),
+        };
+    }
+    vetting::approve(ctx, data, moderator, confession_id).await
+}
+
+pub async fn open_edit_modal(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let confession =
+        match operations::confessions::get_confession(&data.database, confession_id).await {
+            Ok(Some(confession)) => confession,
+            Ok(None) => {
+                vetting::respond_ephemeral(ctx, component, "Confession not found".to_owned()).await;
+                return;
+            }
+            Err(why) => {
+                println!("Error getting confession: {:?}", why);
+                return;
+            }
+        };
+    if ConfessionStatus::from(confession.status) != ConfessionStatus::Pending
+        || vetting::claimed_by_other(&confession, component.user.id)
+    {
+        vetting::respond_ephemeral(ctx, component, vetting::conflict_message(&confession)).await;
+        return;
+    }
+    // Start from what's shown in the vetting message, which includes any earlier edit.
+    let embed = component.message.embeds.first().cloned();
+    let current = embed
+        .as_ref()
+        .and_then(|embed| embed.description.clone())
+        .unwrap_or(confession.content);
+    let modal =
+        util::execute_component_modal(ctx, component, Some(EditModal { content: current }), None)
+            .await;
+    let (modal_interaction, edit_modal) = match modal {
+        Ok(Some(submitted)) => submitted,
+        Ok(None) => return,
+        Err(why) => {
+            println!("Error getting modal: {:?}", why);
+            return;
+        }
+    };
+    let edited_content = edit_modal.content.clone();
+    let response = match submit_edit(
+        ctx,
+        data,
+        component.user.id,
+        confession_id,
+        edit_modal.content,
+    )
+    .await
+    {
+        Ok(Approval::Accepted(tally, post_at)) => modal_interaction
+            .create_interaction_response(&ctx.http, |response_builder| {
+                response_builder.interaction_response_data(|response_data| {
+                    response_data
+                        .content(format!(
+                            "{}, edited by <@{}>",
+                            vetting::accepted_content(&tally, post_at),
+                            component.user.id
+                        ))
+                        .allowed_mentions(|mentions| mentions.empty_parse())
+                })
+            })
+            .await
+            .map(|_| true),
+        Ok(Approval::Waiting(content)) => modal_interaction
+            .create_interaction_response(&ctx.http, |response_builder| {
+                response_builder
+                    .kind(serenity::InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|response_data| {
+                        if let Some(embed) = embed {
+                            let mut edited_embed = serenity::CreateEmbed::from(embed);
+                            edited_embed.description(&edited_content);
+                            response_data.set_embed(edited_embed);
+                        }
+                        response_data
+                            .content(content)
+                            .allowed_mentions(|mentions| mentions.empty_parse())
+                    })
+            })
+            .await
+            .map(|_| false),
+        Err(e) => modal_interaction
+            .create_interaction_response(&ctx.http, |response_builder| {
+                response_builder.interaction_response_data(|response_data| {
+                    response_data
+                        .content(format!("Error approving confession: {}", e))
+                        .ephemeral(true)
+                })
+            })
+            .await
+            .map(|_| false),
+    };
+    match response {
+        Ok(true) => {
+            super::clear_components(ctx, component.channel_id, component.message.id).await;
+        }
+        Ok(false) => {}
+        Err(why) => println!("Error sending message: {:?}", why),
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    auth, filter,
+    operations::{
+        self,
+        filters::{FilterAction, FilterKind},
+    },
+    Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn add_filter(
+    ctx: Context<'_>,
+    #[description = "What to look for"] kind: FilterKind,
+    #[description = "What to do with a matching confession"] action: FilterAction,
+    #[description = "Word, regex or line count, depending on the kind"] pattern: Option<String>,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let pattern = match kind {
+        FilterKind::Word => pattern.map(|word| word.trim().to_owned()),
+        _ => pattern,
+    }
+    .filter(|pattern| !pattern.trim().is_empty());
+    let invalid = match kind {
+        FilterKind::Word | FilterKind::Regex if pattern.is_none() => {
+            Some(format!("A {} filter needs a pattern.", kind))
+        }
+        FilterKind::MaxLines if filter::max_lines(pattern.as_deref()).is_none() => {
+            Some(format!("A {} filter needs a number as its pattern.", kind))
+        }
+        _ => match filter::rule_regex(kind, pattern.as_deref()) {
+            Ok(Some(regex)) if regex.is_match("") => Some(
+                "That pattern matches an empty confession, so it would catch every confession."
+                    .to_owned(),
+            ),
+            Ok(_) => None,
+            Err(e) => Some(format!("Invalid regex: {}", e)),
+        },
+    };
+    if let Some(invalid) = invalid {
+        ctx.say(invalid).await?;
+        return Ok(());
+    }
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    match operations::filters::add_guild_filter(&db, this_guild, kind, pattern, action).await {
+        Ok(id) => {
+            ctx.say(format!("Added {} filter {} ({}).", kind, id, action))
+                .await?;
+        }
+        Err(why) => {
+            ctx.say(format!("Error adding filter: {}", why)).await?;
+        }
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn get_filters(ctx: Context<'_>) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let filters = operations::filters::get_guild_filters(&db, this_guild).await?;
+    if filters.is_empty() {
+        ctx.say("There are no filters set.").await?;
+        return Ok(());
+    }
+    let filters_string = filters
+        .iter()
+        .map(|rule| {
+            format!(
+                "- {}: {} {}{}",
+                rule.id,
+                FilterAction::from(rule.action),
+                FilterKind::from(rule.kind),
+                rule.pattern
+                    .as_ref()
+                    .map(|pattern| format!(" `{}`", pattern))
+                    .unwrap_or_default()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    ctx.send(|builder| {
+        builder
+            .content(format!("Filters:\n{}", filters_string))
+            .ephemeral(true)
+    })
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn remove_filter(
+    ctx: Context<'_>,
+    #[description = "Filter ID from get_filters"] id: i32,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    if let Err(why) = operations::filters::remove_guild_filter(&db, this_guild, id).await {
+        ctx.say(format!("Error removing filter: {}", why)).await?;
+    } else {
+        ctx.say(format!("Removed filter {}.", id)).await?;
+    }
+    Ok(())
+}
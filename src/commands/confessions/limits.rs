@@ -0,0 +1,279 @@
+use poise::serenity_prelude as serenity;
+
+use crate::{auth, entity::guild_rate_limits, operations, Data};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+fn describe_limit(limit: &guild_rate_limits::Model) -> String {
+    let scope = match limit.channel_id {
+        Some(channel_id) => format!("<#{}>", channel_id),
+        None => "Server-wide".to_owned(),
+    };
+    let mut rules = vec![];
+    if limit.max_confessions > 0 && limit.period_minutes > 0 {
+        rules.push(format!(
+            "{} confession(s) every {} minute(s)",
+            limit.max_confessions, limit.period_minutes
+        ));
+    }
+    if limit.max_pending > 0 {
+        rules.push(format!("{} pending at a time", limit.max_pending));
+    }
+    if rules.is_empty() {
+        rules.push("no limit".to_owned());
+    }
+    format!("- {}: {}", scope, rules.join(", "))
+}
+
+// `sent` is oldest first. A slot frees up once enough of the oldest have aged out of the period.
+fn free_slot_at(
+    sent: &[chrono::DateTime<chrono::Utc>],
+    limit: &guild_rate_limits::Model,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let max_confessions = limit.max_confessions as usize;
+    match sent.len() >= max_confessions {
+        true => Some(
+            sent[sent.len() - max_confessions]
+                + chrono::Duration::minutes(limit.period_minutes as i64),
+        ),
+        false => None,
+    }
+}
+
+fn limit_reached(until: chrono::DateTime<chrono::Utc>) -> String {
+    format!(
+        "You have reached the confession limit. You can confess again <t:{}:R>.",
+        until.timestamp()
+    )
+}
+
+// Returns a message for the confessor if any limit covering this channel has been reached.
+pub async fn check_rate_limits(
+    data: &Data,
+    guild_id: u64,
+    channel_id: u64,
+    author_hash: &str,
+) -> anyhow::Result<Option<String>> {
+    let limits =
+        operations::rate_limits::get_rate_limits_for_channel(&data.database, guild_id, channel_id)
+            .await?;
+    let now = chrono::Utc::now();
+    let mut wait_until: Option<chrono::DateTime<chrono::Utc>> = None;
+    for limit in limits {
+        if limit.max_pending > 0 {
+            let pending = operations::confessions::count_author_pending(
+                &data.database,
+                guild_id,
+                author_hash,
+                limit.channel_id,
+            )
+            .await?;
+            if pending >= limit.max_pending as u64 {
+                return Ok(Some(format!(
+                    "You already have {} confession(s) waiting to be vetted. You can confess again once they have been vetted.",
+                    pending
+                )));
+            }
+        }
+        if limit.max_confessions > 0 && limit.period_minutes > 0 {
+            let period = chrono::Duration::minutes(limit.period_minutes as i64);
+            let recent = operations::confessions::get_author_confessions_since(
+                &data.database,
+                guild_id,
+                author_hash,
+                limit.channel_id,
+                now - period,
+            )
+            .await?;
+            let sent = recent
+                .iter()
+                .map(|confession| confession.created_at)
+                .collect::<Vec<chrono::DateTime<chrono::Utc>>>();
+            if let Some(until) = free_slot_at(&sent, &limit) {
+                wait_until = Some(wait_until.map_or(until, |current| current.max(until)));
+            }
+        }
+    }
+    Ok(wait_until.map(limit_reached))
+}
+
+// Direct confessions and the sender's messages after them aren't in a channel, so only the
+// server-wide limit covers them.
+pub async fn check_direct_rate_limits(
+    data: &Data,
+    guild_id: u64,
+    author_hash: &str,
+) -> anyhow::Result<Option<String>> {
+    let limits = operations::rate_limits::get_guild_rate_limits(&data.database, guild_id).await?;
+    let now = chrono::Utc::now();
+    for limit in limits.iter().filter(|limit| limit.channel_id.is_none()) {
+        if limit.max_pending > 0 {
+            let pending = operations::direct_confessions::count_author_pending_direct(
+                &data.database,
+                guild_id,
+                author_hash,
+            )
+            .await?;
+            if pending >= limit.max_pending as u64 {
+                return Ok(Some(format!(
+                    "You already have {} direct confession(s) waiting to be vetted. You can confess again once they have been vetted.",
+                    pending
+                )));
+            }
+        }
+        if limit.max_confessions > 0 && limit.period_minutes > 0 {
+            let sent = operations::direct_confessions::get_author_direct_times_since(
+                &data.database,
+                guild_id,
+                author_hash,
+                now - chrono::Duration::minutes(limit.period_minutes as i64),
+            )
+            .await?;
+            if let Some(until) = free_slot_at(&sent, limit) {
+                return Ok(Some(limit_reached(until)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// Replies aren't confessions to a channel either, so they share the server-wide limit's numbers.
+pub async fn check_reply_rate_limits(
+    data: &Data,
+    guild_id: u64,
+    guild_author_hash: &str,
+) -> anyhow::Result<Option<String>> {
+    let limits = operations::rate_limits::get_guild_rate_limits(&data.database, guild_id).await?;
+    let now = chrono::Utc::now();
+    for limit in limits.iter().filter(|limit| limit.channel_id.is_none()) {
+        if limit.max_pending > 0 {
+            let pending = operations::replies::count_author_pending_replies(
+                &data.database,
+                guild_author_hash,
+            )
+            .await?;
+            if pending >= limit.max_pending as u64 {
+                return Ok(Some(format!(
+                    "You already have {} reply(s) waiting to be vetted. You can reply again once they have been vetted.",
+                    pending
+                )));
+            }
+        }
+        if limit.max_confessions > 0 && limit.period_minutes > 0 {
+            let sent = operations::replies::get_author_reply_times_since(
+                &data.database,
+                guild_author_hash,
+                now - chrono::Duration::minutes(limit.period_minutes as i64),
+            )
+            .await?;
+            if let Some(until) = free_slot_at(&sent, limit) {
+                return Ok(Some(format!(
+                    "You have reached the reply limit. You can reply again <t:{}:R>.",
+                    until.timestamp()
+                )));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_rate_limit(
+    ctx: Context<'_>,
+    #[description = "Confessions allowed per period (0 for no limit)"] confessions: u32,
+    #[description = "Length of the period in minutes"] period_minutes: u32,
+    #[description = "Confessions allowed to wait for vetting at once (0 for no limit)"]
+    pending: u32,
+    #[description = "Only limit this channel"] channel: Option<serenity::ChannelId>,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let channel_id = channel.map(|c| c.0);
+    match operations::rate_limits::set_rate_limit(
+        &db,
+        this_guild,
+        channel_id,
+        confessions,
+        period_minutes,
+        pending,
+    )
+    .await
+    {
+        Ok(_) => {
+            ctx.say(format!(
+                "Set rate limit:\n{}",
+                describe_limit(&guild_rate_limits::Model {
+                    id: 0,
+                    guild_id: this_guild,
+                    channel_id,
+                    max_confessions: confessions,
+                    period_minutes,
+                    max_pending: pending,
+                })
+            ))
+            .await?;
+        }
+        Err(why) => {
+            ctx.say(format!("Error setting rate limit: {}", why))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn get_rate_limits(ctx: Context<'_>) -> Result<(), Error> {
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let limits = operations::rate_limits::get_guild_rate_limits(&db, this_guild).await?;
+    if limits.is_empty() {
+        ctx.say("There are no rate limits set.").await?;
+        return Ok(());
+    }
+    let limits_string = limits
+        .iter()
+        .map(describe_limit)
+        .collect::<Vec<String>>()
+        .join("\n");
+    ctx.say(format!("Rate limits:\n{}", limits_string)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn remove_rate_limit(
+    ctx: Context<'_>,
+    #[description = "Only this channel's limit"] channel: Option<serenity::ChannelId>,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    if let Err(why) =
+        operations::rate_limits::remove_rate_limit(&db, this_guild, channel.map(|c| c.0)).await
+    {
+        ctx.say(format!("Error removing rate limit: {}", why))
+            .await?;
+    } else {
+        ctx.say("Removed rate limit.").await?;
+    }
+    Ok(())
+}
@@ -1,17 +1,17 @@
 use ::serenity::futures::StreamExt;
 use anyhow::anyhow;
 use poise::{execute_modal, serenity_prelude as serenity, Modal};
-use serde::{Deserialize, Serialize};
 use tracing::info;
 
-use std::mem;
 use std::{hash::Hasher, time::Duration};
 use twox_hash::XxHash64;
 
 // this is a blank struct initialised in main.rs and then imported here
 use crate::{
-    auth, button,
-    operations::{self, guild_confessions},
+    auth, button, crypto,
+    entity::{confession_polls, confessions},
+    filter, media,
+    operations::{self, guild::DuplicateAction, guild_confessions, pseudonyms},
     Data,
 };
 
@@ -19,7 +19,25 @@ type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
 type FrameworkContext<'a> = poise::FrameworkContext<'a, Data, Error>;
 
+pub mod ask;
+pub mod blocks;
+pub mod deny;
+pub mod direct;
+pub mod duplicates;
+pub mod edit;
+pub mod filters;
+pub mod limits;
+pub mod modmail;
+pub mod polls;
+pub mod queue;
+pub mod replies;
+pub mod schedule;
+pub mod subjects;
+pub mod vetting;
+pub mod warnings;
+
 use super::super::operations::channels::ChannelUse;
+use super::super::operations::confessions::ConfessionStatus;
 
 #[derive(Debug, Modal)]
 #[name = "Input"]
@@ -29,171 +47,346 @@ struct ConfessionModal {
     #[max_length = 500]
     #[paragraph]
     content: String,
+    #[name = "Content warning (optional)"]
+    #[max_length = 100]
+    content_warning: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConfessionVetInfo {
     author: serenity::User,
     content: String,
-    image: Option<Vec<u8>>,
+    content_warning: Option<String>,
+    subject: Option<String>,
+    attachments: Vec<media::ConfessionFile>,
+    poll: Option<polls::PollDraft>,
+    flags: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ConfessionInfo {
-    author: serenity::User,
-    content: String,
-    image: Option<String>,
+// What the confessor gave, before files are downloaded and filters run.
+#[derive(Debug, Clone, Default)]
+pub struct ConfessionInput {
+    content: Option<String>,
+    content_warning: Option<String>,
+    subject: Option<String>,
+    files: Vec<serenity::Attachment>,
+    spoiler: bool,
+    poll: Option<polls::PollDraft>,
 }
 
 fn to_user(col: u64) -> u32 {
     const MAX: u64 = 16_777_215; // Maximum color value (0xFFFFFF)
-    return unsafe { mem::transmute::<u64, [u32; 2]>(col % MAX) }[0];
+    (col % MAX) as u32
 }
 
-pub async fn get_guild_confession_hash(db: &sea_orm::DatabaseConnection, guild_id: u64) -> u64 {
-    let guild_confessions = guild_confessions::get_or_new_guild_confessions(&db, guild_id).await;
-    guild_confessions.unwrap().hash
-}
+pub const MAX_PSEUDONYM_ATTEMPTS: u32 = 32;
 
 pub fn get_hash_from_user(guild_confession_hash: u64, user: serenity::UserId) -> u32 {
+    get_hash_candidate(guild_confession_hash, user, 0)
+}
+
+// The first candidate is the plain hash so existing pseudonyms stay the same.
+pub fn get_hash_candidate(guild_confession_hash: u64, user: serenity::UserId, attempt: u32) -> u32 {
     let mut hasher = XxHash64::with_seed(guild_confession_hash);
-    hasher.write_u64(user.0);
+    hasher.write(&user.0.to_le_bytes());
+    if attempt > 0 {
+        hasher.write(&attempt.to_le_bytes());
+    }
     to_user(hasher.finish())
 }
 
+pub async fn get_pseudonym_for_user(
+    db: &sea_orm::DatabaseConnection,
+    key: &crypto::AuthorKey,
+    guild_id: u64,
+    user: serenity::UserId,
+) -> anyhow::Result<u32> {
+    let epoch = guild_confessions::get_or_new_guild_confessions(db, guild_id)
+        .await?
+        .hash;
+    let author_hash = crypto::hash_author(key, guild_id, user.0);
+    for attempt in 0..MAX_PSEUDONYM_ATTEMPTS {
+        if let Some(pseudonym) =
+            pseudonyms::get_author_pseudonym(db, guild_id, epoch, author_hash.clone()).await?
+        {
+            return Ok(pseudonym);
+        }
+        let candidate = get_hash_candidate(epoch, user, attempt);
+        if pseudonyms::is_pseudonym_taken(db, guild_id, epoch, candidate).await? {
+            continue;
+        }
+        if pseudonyms::add_pseudonym(db, guild_id, epoch, author_hash.clone(), candidate)
+            .await
+            .is_ok()
+        {
+            return Ok(candidate);
+        }
+    }
+    Err(anyhow!("Could not find a free pseudonym. Try `/shuffle`."))
+}
+
+pub async fn new_confession(
+    db: &sea_orm::DatabaseConnection,
+    key: &crypto::AuthorKey,
+    guild_id: u64,
+    target_channel: serenity::ChannelId,
+    author: serenity::UserId,
+    content: String,
+) -> anyhow::Result<confessions::Model> {
+    let pseudonym = get_pseudonym_for_user(db, key, guild_id, author).await?;
+    let author_ref = crypto::encrypt_author(key, author.0)?;
+    operations::confessions::add_confession(
+        db,
+        guild_id,
+        target_channel.0,
+        author_ref,
+        crypto::hash_author(key, guild_id, author.0),
+        pseudonym,
+        content,
+    )
+    .await
+}
+
+// Returns false if the channel let the confession skip vetting and it was posted directly.
 pub async fn send_verify_confession(
     ctx: Context<'_>,
     target_channel: serenity::ChannelId,
     info: ConfessionVetInfo,
-) {
+) -> anyhow::Result<bool> {
     let guild = ctx.guild_id().unwrap();
-    let vetting_channel = operations::channels::get_channels_in_guild_with_use(
+    let vetting_channels = match operations::channels::get_channels_in_guild_with_use(
         &ctx.data().database,
         guild.0,
         ChannelUse::Vetting,
     )
-    .await;
-    if let Err(why) = vetting_channel {
-        if let Err(why_msg) = ctx
-            .send(|builder| {
-                builder
-                    .content(format!(
-                        "Error getting vetting channel: {:?}",
-                        why.to_string()
-                    ))
-                    .ephemeral(true)
-                    .reply(true)
-            })
-            .await
-        {
-            println!("Error sending message: {:?}", why_msg);
-        }
-        return;
-    }
-    let vetting_channels = vetting_channel.unwrap();
-    match vetting_channels.get(0) {
+    .await
+    {
+        Ok(vetting_channels) => vetting_channels,
+        Err(why) => return Err(anyhow!("Error getting vetting channel: {}", why)),
+    };
+    match vetting_channels.first() {
         Some(channel_model) => {
             let channel_id = serenity::ChannelId::from(channel_model.id);
-            let show_id = get_hash_from_user(
-                get_guild_confession_hash(&ctx.data().database, guild.0).await,
+            let confession_res = new_confession(
+                &ctx.data().database,
+                &ctx.data().author_key,
+                guild.0,
+                target_channel,
                 info.author.id,
-            );
-            if let Err(why) = ctx.defer_ephemeral().await {
-                println!("Error deferring message: {:?}", why);
-            };
-            let mut files = vec![];
-            if let Some(img) = &info.image {
-                files.push((&img as &[u8], "image.png"));
+                info.content.clone(),
+            )
+            .await;
+            let mut confession = match confession_res {
+                Ok(confession) => confession,
+                Err(why) => return Err(anyhow!("Error saving confession: {}", why)),
             };
-            if let Err(why) = channel_id
+            let show_id = confession.pseudonym;
+            let quorum = operations::guild::get_guild_vetting_quorum(&ctx.data().database, guild.0)
+                .await
+                .unwrap_or((1, 1));
+            let roles = ctx
+                .author_member()
+                .await
+                .map(|member| member.roles.clone())
+                .unwrap_or_default();
+            // Flagged confessions always go to the vetting channel.
+            let mut vetted = !info.flags.is_empty()
+                || vetting::needs_vetting(
+                    ctx.data(),
+                    guild.0,
+                    target_channel.0,
+                    &confession.author_hash,
+                    &roles,
+                )
+                .await
+                .unwrap_or(true);
+            let files = info
+                .attachments
+                .iter()
+                .map(|file| file.to_attachment())
+                .collect::<Vec<serenity::AttachmentType>>();
+            let sent = channel_id
                 .send_files(&ctx, files, |m| {
                     m.embed(|embed| {
                         embed
-                            .description(&info.content)
+                            .title(format!("Confession #{}", confession.number))
                             .author(|a| a.name(format!("[{:x}]", show_id)))
                             .colour(show_id);
-                        if let Some(_) = &info.image {
-                            embed.image("attachment://image.png");
+                        if !info.content.is_empty() {
+                            embed.description(&info.content);
+                        }
+                        if let Some(image) = media::embed_image(&info.attachments) {
+                            embed.image(image);
+                        }
+                        if let Some(poll) = &info.poll {
+                            embed.field("Poll", polls::describe_options(poll), false);
+                        }
+                        if let Some(content_warning) = &info.content_warning {
+                            embed.field(warnings::CONTENT_WARNING_FIELD, content_warning, false);
+                        }
+                        if let Some(subject) = &info.subject {
+                            embed.field(subjects::SUBJECT_FIELD, subject, true);
+                        }
+                        if !info.flags.is_empty() {
+                            embed.field("Flags", info.flags.join("\n"), false);
                         }
                         embed
                     })
-                    .content(format!(
-                        "Confession going to <#{}>",
-                        target_channel.0
-                    ))
+                    .content(if vetted {
+                        vetting::vetting_content(&confession, &vetting::Tally::default(), quorum)
+                    } else {
+                        format!("Accepted for <#{}> without vetting", confession.channel_id)
+                    })
                     .components(|components| {
-                        components.create_action_row(|action_row| {
-                            action_row
-                                .add_button(
-                                    serenity::CreateButton::default()
-                                        .label("Approve")
-                                        .style(serenity::ButtonStyle::Success)
-                                        .custom_id(
-                                            button::ConfessionButton::ApproveConfession((
-                                                info.author.id,
-                                                target_channel,
-                                            ))
-                                            .to_string(),
-                                        )
-                                        .to_owned(),
-                                )
-                                .add_button(
-                                    serenity::CreateButton::default()
-                                        .label("Deny")
-                                        .style(serenity::ButtonStyle::Danger)
-                                        .custom_id(
-                                            button::ConfessionButton::DenyConfession.to_string(),
-                                        )
-                                        .to_owned(),
-                                )
-                        })
+                        if vetted {
+                            vetting::add_vetting_buttons(components, confession.id);
+                        }
+                        components
                     })
                 })
-                .await
-            {
-                println!("Error sending message: {:?}", why);
-            }
-        }
-        None => {
-            if let Err(why_msg) = ctx
-                .send(|builder| {
-                    builder
-                        .content(format!(
-                            "There is no vetting channel set. Use `/set_vetting` to set one."
-                        ))
-                        .ephemeral(true)
-                        .reply(true)
-                })
-                .await
-            {
-                println!("Error sending message: {:?}", why_msg);
+                .await;
+            match sent {
+                Ok(message) => {
+                    confession.vetting_message_id = Some(message.id.0);
+                    confession.content_warning = info.content_warning;
+                    confession.subject = info.subject;
+                    if let Some(poll) = info.poll {
+                        if let Err(why) = operations::polls::add_poll(
+                            &ctx.data().database,
+                            confession.id,
+                            poll.options,
+                            poll.duration_hours,
+                        )
+                        .await
+                        {
+                            println!("Error saving poll: {:?}", why);
+                        }
+                    }
+                    // Kept so approval can upload the files again rather than link to the vetting channel.
+                    for (position, file) in info.attachments.into_iter().enumerate() {
+                        if let Err(why) = operations::attachments::add_attachment(
+                            &ctx.data().database,
+                            confession.id,
+                            position as i32,
+                            file.filename,
+                            file.content_type,
+                            file.bytes,
+                            file.spoiler,
+                        )
+                        .await
+                        {
+                            println!("Error saving attachment: {:?}", why);
+                        }
+                    }
+                    match operations::confessions::set_confession(&ctx.data().database, confession)
+                        .await
+                    {
+                        Ok(confession) if !vetted => {
+                            vetted = schedule::post_unvetted(
+                                ctx.serenity_context(),
+                                ctx.data(),
+                                confession,
+                            )
+                            .await
+                            .map_err(|why| anyhow!("Error posting confession: {}", why))?;
+                        }
+                        Ok(_) => {}
+                        Err(why) => return Err(anyhow!("Error saving confession: {}", why)),
+                    }
+                }
+                Err(why) => return Err(anyhow!("Error sending confession to be vetted: {}", why)),
             }
+            Ok(vetted)
         }
+        None => Err(anyhow!(
+            "There is no vetting channel set. Use `/set_vetting` to set one."
+        )),
     }
 }
 
 pub async fn _confess_to(
     ctx: &Context<'_>,
     channel: serenity::ChannelId,
-    input_content: Option<String>,
-    input_image: Option<serenity::Attachment>,
+    input: ConfessionInput,
 ) -> Result<(), Error> {
+    let ConfessionInput {
+        content: input_content,
+        content_warning: input_content_warning,
+        subject: input_subject,
+        files: input_files,
+        spoiler,
+        poll,
+    } = input;
     let channel_usage_result = operations::channels::get_channel_use(
         &ctx.data().database,
         ctx.guild_id().unwrap().0,
         channel.0,
     )
     .await;
-    let mut content = input_content.or(match &input_image {
-        Some(image) => Some(format!("[Filename: {}]", image.filename)),
-        None => None,
-    });
+    let this_guild = ctx.guild_id().unwrap().0;
+    let author_hash = crypto::hash_author(&ctx.data().author_key, this_guild, ctx.author().id.0);
+    // Blocked members can still report to staff, since a block is when they may most need to.
+    let blocked = match channel_usage_result {
+        Ok(ChannelUse::Modmail) => Ok(None),
+        _ => blocks::check_blocked(ctx.data(), this_guild, &author_hash).await,
+    };
+    let refused = match blocked {
+        Ok(None) => {
+            limits::check_rate_limits(ctx.data(), this_guild, channel.0, &author_hash).await
+        }
+        blocked => blocked,
+    };
+    match refused {
+        Ok(Some(refusal)) => {
+            if let Err(why) = ctx
+                .send(|builder| builder.content(refusal).ephemeral(true).reply(true))
+                .await
+            {
+                info!("Error sending message: {:?}", why);
+            }
+            return Ok(());
+        }
+        Ok(None) => {}
+        Err(why) => println!("Error checking confession limits: {:?}", why),
+    }
+    let guild_model = operations::guild::get_guild(&ctx.data().database, this_guild)
+        .await
+        .unwrap_or_default();
+    let attachment_rules = media::AttachmentRules::from_guild(guild_model.as_ref());
+    if input_files.len() as u32 > attachment_rules.max_attachments {
+        if let Err(why) = ctx
+            .send(|builder| {
+                builder
+                    .content(format!(
+                        "You can attach at most {} file(s) in this server.",
+                        attachment_rules.max_attachments
+                    ))
+                    .ephemeral(true)
+                    .reply(true)
+            })
+            .await
+        {
+            info!("Error sending message: {:?}", why);
+        }
+        return Ok(());
+    }
+    // Files can stand on their own, so only ask for text when there are none.
+    let mut content = match input_files.is_empty() {
+        true => input_content,
+        false => Some(input_content.unwrap_or_default()),
+    };
+    let mut content_warning = input_content_warning;
     if let None = content {
         content = match ctx {
             poise::Context::Application(app) => {
                 let modal = execute_modal::<_, _, ConfessionModal>(*app, None, None).await;
                 if let Ok(modal_result) = modal {
-                    modal_result.map(|m| m.content)
+                    modal_result.map(|m| {
+                        if content_warning.is_none() {
+                            content_warning = m.content_warning;
+                        }
+                        m.content
+                    })
                 } else {
                     None
                 }
@@ -201,36 +394,106 @@ pub async fn _confess_to(
             poise::Context::Prefix(_) => None,
         };
     };
+    // Everything past here can take longer than Discord waits for a first response.
+    if let Err(why) = ctx.defer_ephemeral().await {
+        println!("Error deferring message: {:?}", why);
+    };
+    let content_warning = warnings::clean_content_warning(content_warning);
+    let needs_content_warning = operations::channels::get_channel_requires_content_warning(
+        &ctx.data().database,
+        this_guild,
+        channel.0,
+    )
+    .await
+    .unwrap_or(false);
+    let subject_missing = match &input_subject {
+        Some(subject) => operations::subjects::guild_has_subject(
+            &ctx.data().database,
+            this_guild,
+            subject.clone(),
+        )
+        .await
+        .map(|found| found.is_none())
+        .unwrap_or(true),
+        None => false,
+    };
     // get a modal to send to the user
     let response = match channel_usage_result {
         Ok(channel_type) => {
-            match channel_type == ChannelUse::Confession {
-                true => {
-                    let image_data = match input_image {
-                        Some(found_image) => {
-                            // filter non MIME-type images
-                            if !found_image.content_type.clone().unwrap().starts_with("image/") {
-                                None
-                            } else {
-                                match found_image.download().await {
-                                    Ok(image_data) => Some(image_data),
-                                    Err(_) => None,
-                                }
+            match channel_type {
+                ChannelUse::AskMe if !input_files.is_empty() || poll.is_some() => format!("Questions to <#{}> can't include files or polls.", channel),
+                ChannelUse::Modmail if !input_files.is_empty() || poll.is_some() => format!("Reports to <#{}> can't include files or polls.", channel),
+                ChannelUse::Modmail => match content.filter(|content| !content.trim().is_empty()) {
+                    Some(content) => match modmail::open_report(ctx, channel, content).await {
+                        Ok(response) => response,
+                        Err(e) => format!("Error sending report: {}\nYour report has not been processed.", e),
+                    },
+                    None => "Your report was empty and has not been sent.".to_owned(),
+                },
+                ChannelUse::Confession if subject_missing => format!("{} isn't a subject in this server. Use `/get_subjects` to see them.", input_subject.unwrap_or_default()),
+                ChannelUse::Confession | ChannelUse::AskMe if needs_content_warning && content_warning.is_none() => format!("Confessions to <#{}> need a content warning. Add one with the `content_warning` option.", channel),
+                ChannelUse::Confession | ChannelUse::AskMe => {
+                    let mut attachments = Ok(vec![]);
+                    for (position, found_file) in input_files.iter().enumerate() {
+                        attachments = match attachments {
+                            Ok(mut files) => media::download_attachment(found_file, &attachment_rules, position, spoiler)
+                                .await
+                                .map(|file| {
+                                    files.push(file);
+                                    files
+                                }),
+                            failed => failed,
+                        };
+                    }
+                    let rules = operations::filters::get_guild_filters(&ctx.data().database, this_guild).await;
+                    let mut filtered = filter::apply_filters(rules.as_deref().unwrap_or_default(), &content.unwrap_or("?".to_owned()));
+                    let mut poll = poll;
+                    if let Some(poll) = poll.as_mut() {
+                        polls::filter_options(rules.as_deref().unwrap_or_default(), poll, &mut filtered);
+                    }
+                    // Without the rules nothing was checked, so a moderator has to look at it instead.
+                    if let Err(why) = rules {
+                        println!("Error getting filters: {:?}", why);
+                        filtered.flags.push("Filters could not be checked".to_owned());
+                    }
+                    if filtered.rejection.is_none() {
+                        match duplicates::find_duplicate(ctx.data(), this_guild, &filtered.content).await {
+                            Ok(Some((DuplicateAction::Reject, _))) => {
+                                filtered.rejection = Some("it is too similar to an earlier confession".to_owned());
                             }
-                        },
-                        None => None,
-                    };
-                    send_verify_confession(
-                        *ctx,
-                        channel,
-                        ConfessionVetInfo {
-                            author: ctx.author().clone(),
-                            content: content.unwrap_or("?".to_owned()), 
-                            image: image_data
-                        }).await;
-                    format!("Your confession has been sent to be vetted.")
+                            Ok(Some((_, duplicate))) => filtered.flags.push(format!("Near-duplicate of {}", duplicate)),
+                            Ok(None) => {}
+                            Err(why) => println!("Error checking for duplicates: {:?}", why),
+                        }
+                    }
+                    match (attachments, filtered.rejection) {
+                        (Err(why), _) => format!("Your attachment can't be used: {}\nYour confession has not been processed.", why),
+                        (_, Some(rejection)) => format!("Your confession was rejected because {}.", rejection),
+                        (Ok(attachments), None) => {
+                            let metadata_note = match attachments.iter().any(|file| file.keeps_metadata()) {
+                                true => "\nOnly images and MP4, MOV and M4A files have their metadata removed. Your other files were sent as you uploaded them.",
+                                false => "",
+                            };
+                            match send_verify_confession(
+                                *ctx,
+                                channel,
+                                ConfessionVetInfo {
+                                    author: ctx.author().clone(),
+                                    content: filtered.content,
+                                    content_warning,
+                                    subject: input_subject,
+                                    attachments,
+                                    poll,
+                                    flags: filtered.flags,
+                                }).await {
+                                Ok(true) => format!("Your confession has been sent to be vetted.{}", metadata_note),
+                                Ok(false) => format!("Your confession has been accepted.{}", metadata_note),
+                                Err(e) => format!("{}\nYour confession has not been processed.", e),
+                            }
+                        }
+                    }
                 },
-                false => format!("This channel (<#{}>) is not for confessing. Use `/list` to find places to confess.", ctx.channel_id()),
+                _ => format!("This channel (<#{}>) is not for confessing. Use `/list` to find places to confess.", ctx.channel_id()),
             }
         }
         Err(e) => format!(
@@ -254,13 +517,59 @@ pub async fn _confess_to(
     description_localized("en-US", "Confess to a channel"),
     guild_only = true
 )]
+// Poise turns each parameter into a command option, so they can't be grouped into a struct.
+#[allow(clippy::too_many_arguments)]
 pub async fn confess_to(
     ctx: Context<'_>,
-    #[description = "Channel to confess to"] channel: serenity::ChannelId,
+    #[description = "Channel to confess to"]
+    #[autocomplete = "crate::commands::autocomplete::confession_channel"]
+    channel: String,
     #[description = "Content"] content: Option<String>,
-    #[description = "An image"] image: Option<serenity::Attachment>,
+    #[description = "A file. Metadata is removed from images and MP4, MOV and M4A files only"]
+    file: Option<serenity::Attachment>,
+    #[description = "Another file"] file_2: Option<serenity::Attachment>,
+    #[description = "Another file"] file_3: Option<serenity::Attachment>,
+    #[description = "Another file"] file_4: Option<serenity::Attachment>,
+    #[description = "Mark the files as spoilers"] spoiler: Option<bool>,
+    #[description = "Warning shown before the confession"] content_warning: Option<String>,
+    #[description = "What the confession is about"]
+    #[autocomplete = "crate::commands::autocomplete::subject"]
+    subject: Option<String>,
 ) -> Result<(), Error> {
-    _confess_to(&ctx, channel, content, image).await
+    let files = [file, file_2, file_3, file_4]
+        .into_iter()
+        .flatten()
+        .collect();
+    // Autocomplete fills in the id, but a typed mention works too.
+    let channel = match channel
+        .trim_start_matches("<#")
+        .trim_end_matches('>')
+        .parse::<u64>()
+    {
+        Ok(channel_id) => serenity::ChannelId(channel_id),
+        Err(_) => {
+            ctx.send(|builder| {
+                builder
+                    .content("Pick a confession channel from the list.")
+                    .ephemeral(true)
+            })
+            .await?;
+            return Ok(());
+        }
+    };
+    _confess_to(
+        &ctx,
+        channel,
+        ConfessionInput {
+            content,
+            content_warning,
+            subject,
+            files,
+            spoiler: spoiler.unwrap_or(false),
+            poll: None,
+        },
+    )
+    .await
 }
 
 #[poise::command(
@@ -270,12 +579,38 @@ pub async fn confess_to(
     description_localized("en-US", "Confesses to the current channel."),
     guild_only = true
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn confess(
     ctx: Context<'_>,
     #[description = "Content"] content: Option<String>,
-    #[description = "An image"] image: Option<serenity::Attachment>,
+    #[description = "A file. Metadata is removed from images and MP4, MOV and M4A files only"]
+    file: Option<serenity::Attachment>,
+    #[description = "Another file"] file_2: Option<serenity::Attachment>,
+    #[description = "Another file"] file_3: Option<serenity::Attachment>,
+    #[description = "Another file"] file_4: Option<serenity::Attachment>,
+    #[description = "Mark the files as spoilers"] spoiler: Option<bool>,
+    #[description = "Warning shown before the confession"] content_warning: Option<String>,
+    #[description = "What the confession is about"]
+    #[autocomplete = "crate::commands::autocomplete::subject"]
+    subject: Option<String>,
 ) -> Result<(), Error> {
-    _confess_to(&ctx, ctx.channel_id(), content, image).await
+    let files = [file, file_2, file_3, file_4]
+        .into_iter()
+        .flatten()
+        .collect();
+    _confess_to(
+        &ctx,
+        ctx.channel_id(),
+        ConfessionInput {
+            content,
+            content_warning,
+            subject,
+            files,
+            spoiler: spoiler.unwrap_or(false),
+            poll: None,
+        },
+    )
+    .await
 }
 
 #[poise::command(slash_command, prefix_command, guild_only = true)]
@@ -330,10 +665,12 @@ pub async fn set_confessing(ctx: Context<'_>) -> Result<(), Error> {
 
 pub const MOD_MAX_VOTES: usize = 5;
 
-#[poise::command(prefix_command, guild_only = true)]
+#[poise::command(slash_command, prefix_command, guild_only = true)]
 pub async fn vote_reveal(
     ctx: Context<'_>,
-    #[description = "Reveal"] id: String,
+    #[description = "Confession number to reveal"]
+    #[autocomplete = "crate::commands::autocomplete::moderated_confession"]
+    id: u64,
 ) -> Result<(), Error> {
     let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
     if let Err(_) = auth_res {
@@ -356,34 +693,19 @@ pub async fn vote_reveal(
     }
     let the_mods = the_mods.unwrap();
 
-    let numbered_id = u32::from_str_radix(&id, 16);
-    if let Err(_) = numbered_id {
-        ctx.say(format!("Invalid ID: {}", id)).await?;
-        return Ok(());
-    }
-    let guild_confession_hash =
-        get_guild_confession_hash(&ctx.data().database, ctx.guild_id().unwrap().0).await;
-    let found_out = match ctx
-        .partial_guild()
-        .await
-        .unwrap()
-        .members(ctx, None, None)
-        .await
+    let found_out = match operations::confessions::get_confession_by_number(
+        &ctx.data().database,
+        ctx.guild_id().unwrap().0,
+        id,
+    )
+    .await
     {
-        Ok(members) => {
-            let found = members
-                .into_iter()
-                .find(|member| {
-                    get_hash_from_user(guild_confession_hash, member.user.id)
-                        == numbered_id.to_owned().unwrap()
-                })
-                .map(|member| member.user.id);
-            match found {
-                Some(id) => Ok(id),
-                None => Err(anyhow!("Could not find user with ID: {}", id)),
-            }
+        Ok(Some(confession)) => {
+            crypto::decrypt_author(&ctx.data().author_key, &confession.author_ref)
+                .map(serenity::UserId)
         }
-        Err(e) => Err(anyhow!("Error getting members: {}", e.to_string())),
+        Ok(None) => Err(anyhow!("Could not find confession #{}", id)),
+        Err(e) => Err(e),
     };
 
     if let Err(why) = found_out {
@@ -396,13 +718,13 @@ pub async fn vote_reveal(
         .send(|message| {
             message
                 .reply(true)
-                .content(format!("Reveal the user behind `{}`?", id))
+                .content(format!("Reveal the user behind confession #{}?", id))
                 .components(|components| {
                     components.create_action_row(|row| {
                         row.create_button(|button| {
                             button
                                 .custom_id(
-                                    button::ConfessionRevealButton::RevealConfession(id.clone())
+                                    button::ConfessionRevealButton::RevealConfession(id)
                                         .to_string(),
                                 )
                                 .label("Yes")
@@ -562,6 +884,196 @@ pub async fn vote_reveal(
     Ok(())
 }
 
+pub async fn post_confession(
+    ctx: &serenity::Context,
+    data: &Data,
+    mut confession: confessions::Model,
+) -> anyhow::Result<confessions::Model> {
+    let show_id = confession.pseudonym;
+    let mut stored_files =
+        operations::attachments::get_confession_attachments(&data.database, confession.id)
+            .await?
+            .into_iter()
+            .map(media::ConfessionFile::from)
+            .collect::<Vec<media::ConfessionFile>>();
+    // Files would show above the warning, so they are spoilered along with the text.
+    if confession.content_warning.is_some() {
+        for file in stored_files.iter_mut() {
+            file.spoiler = true;
+        }
+    }
+    let files = stored_files
+        .iter()
+        .map(|file| file.to_attachment())
+        .collect::<Vec<serenity::AttachmentType>>();
+    let content = confession
+        .edited_content
+        .as_ref()
+        .unwrap_or(&confession.content);
+    let description = match &confession.content_warning {
+        Some(content_warning) => warnings::hide_behind_warning(content_warning, content),
+        None => content.clone(),
+    };
+    // The deadline runs from posting, not from when the poll was confessed.
+    let poll = operations::polls::get_confession_poll(&data.database, confession.id)
+        .await?
+        .map(|poll| confession_polls::Model {
+            closes_at: Some(
+                chrono::Utc::now() + chrono::Duration::hours(poll.duration_hours as i64),
+            ),
+            ..poll
+        });
+    let subject_recipients = match &confession.subject {
+        Some(subject) => subjects::get_subject_recipients(data, &confession, subject)
+            .await
+            .unwrap_or_else(|why| {
+                println!("Error getting subject recipients: {:?}", why);
+                subjects::SubjectRecipients::default()
+            }),
+        None => subjects::SubjectRecipients::default(),
+    };
+    let posted = serenity::ChannelId(confession.channel_id)
+        .send_files(&ctx, files, |m| {
+            if !subject_recipients.mentions.is_empty() {
+                m.content(subject_recipients.mention_content())
+                    .allowed_mentions(|mentions| {
+                        mentions.users(subject_recipients.mentions.iter().copied())
+                    });
+            }
+            m.embed(|embed| {
+                embed
+                    .title(format!("Confession #{}", confession.number))
+                    .author(|a| a.name(format!("[{:x}]", show_id)))
+                    .colour(show_id);
+                if !description.is_empty() {
+                    embed.description(&description);
+                }
+                match (media::embed_image(&stored_files), &confession.image) {
+                    (Some(image), _) => {
+                        embed.image(image);
+                    }
+                    // Confessions saved before files were stored only have a URL.
+                    (None, Some(image))
+                        if stored_files.is_empty() && confession.content_warning.is_none() =>
+                    {
+                        embed.image(image);
+                    }
+                    _ => {}
+                }
+                if let Some(subject) = &confession.subject {
+                    embed.field(subjects::SUBJECT_FIELD, subject, true);
+                }
+                if let Some(poll) = &poll {
+                    embed.field(
+                        polls::RESULTS_FIELD,
+                        polls::results_text(poll, &operations::polls::get_poll_options(poll), &[]),
+                        false,
+                    );
+                }
+                embed
+            })
+            .components(|components| {
+                if let Some(poll) = &poll {
+                    polls::add_poll_buttons(
+                        components,
+                        poll.id,
+                        &operations::polls::get_poll_options(poll),
+                    );
+                }
+                components.create_action_row(|action_row| {
+                    action_row.add_button(replies::create_reply_button(confession.id))
+                })
+            })
+        })
+        .await?;
+    if let Some(poll) = &poll {
+        if let Some(closes_at) = poll.closes_at {
+            if let Err(why) = operations::polls::open_poll(&data.database, poll.id, closes_at).await
+            {
+                println!("Error opening poll: {:?}", why);
+            }
+        }
+    }
+    if let Some(subject) = &confession.subject {
+        subjects::notify_subscribers(
+            ctx,
+            &confession,
+            subject,
+            &subject_recipients,
+            posted.link(),
+        )
+        .await;
+    }
+    confession.posted_message_id = Some(posted.id.0);
+    confession.status = ConfessionStatus::Approved.into();
+    if !stored_files.is_empty() {
+        confession.image = posted
+            .embeds
+            .first()
+            .and_then(|embed| embed.image.clone())
+            .map(|embed_image| embed_image.url);
+        // The public message holds the files now.
+        if let Err(why) =
+            operations::attachments::remove_confession_attachments(&data.database, confession.id)
+                .await
+        {
+            println!("Error removing attachments: {:?}", why);
+        }
+    }
+    operations::confessions::set_confession(&data.database, confession).await
+}
+
+pub async fn clear_components(
+    ctx: &serenity::Context,
+    channel_id: serenity::ChannelId,
+    message_id: serenity::MessageId,
+) {
+    let mut edit = serenity::EditMessage::default();
+    edit.set_components(serenity::CreateComponents::default());
+
+    // Can't directly use `edit` fsr.
+    if let Err(e) = ctx
+        .http
+        .edit_message_and_attachments(
+            channel_id.0,
+            message_id.0,
+            &::serenity::json::Value::from(::serenity::json::hashmap_to_json_map(edit.0)),
+            vec![],
+        )
+        .await
+    {
+        println!("Error sending message: {:?}", e);
+    };
+}
+
+pub async fn notify_author(
+    ctx: &serenity::Context,
+    data: &Data,
+    confession: &confessions::Model,
+    title: String,
+    reason: Option<String>,
+) -> anyhow::Result<()> {
+    let author_id = crypto::decrypt_author(&data.author_key, &confession.author_ref)?;
+    let dm_channel = serenity::UserId(author_id).create_dm_channel(ctx).await?;
+    dm_channel
+        .send_message(ctx, |m| {
+            m.embed(|embed| {
+                embed
+                    .title(title)
+                    .field("Channel", format!("<#{}>", confession.channel_id), true);
+                if !confession.content.is_empty() {
+                    embed.description(&confession.content);
+                }
+                if let Some(reason) = reason {
+                    embed.field("Reason", reason, false);
+                }
+                embed
+            })
+        })
+        .await?;
+    Ok(())
+}
+
 pub async fn handle<'a>(
     ctx: &serenity::Context,
     ev: &poise::Event<'a>,
@@ -574,124 +1086,104 @@ pub async fn handle<'a>(
                 match crate::button::ConfessionButton::from_string(&component.data.custom_id) {
                     Some(button_interaction) => {
                         let should_clear = match button_interaction {
-                            crate::button::ConfessionButton::ApproveConfession(send_info) => {
-                                let maybe_user = send_info.0.to_user(ctx).await;
-                                if let Err(why_no_user) = maybe_user {
-                                    println!("Error getting user: {:?}", why_no_user);
-                                    return Ok(());
-                                }
-                                let user = maybe_user.unwrap();
-                                let info_opt =
-                                    component.message.embeds.get(0).map(|embed| ConfessionInfo {
-                                        author: user,
-                                        content: embed.description.clone().unwrap_or("".to_owned()),
-                                        image: embed
-                                            .image
-                                            .clone()
-                                            .map(|embed_image| embed_image.url),
-                                    });
-                                let mut valid = false;
-                                match info_opt {
-                                    Some(info) => {
-                                        let show_id = get_hash_from_user(
-                                            get_guild_confession_hash(
-                                                &data.database,
-                                                component.guild_id.unwrap().0,
-                                            )
-                                            .await,
-                                            info.author.id,
-                                        );
-                                        if let Err(why) = send_info
-                                            .1
-                                            .send_message(&ctx, move |m| {
-                                                m.embed(|embed| {
-                                                    embed
-                                                        .description(info.content)
-                                                        .author(|a| {
-                                                            a.name(format!("[{:x}]", show_id))
-                                                        })
-                                                        .colour(show_id);
-                                                    if let Some(image) = info.image {
-                                                        embed.image(image);
-                                                    }
-                                                    embed
-                                                })
-                                            })
-                                            .await
-                                        {
-                                            println!("Error sending message: {:?}", why);
-                                        }
-                                        if let Err(why) = component
-                                            .create_interaction_response(&ctx.http, |response| {
-                                                response.interaction_response_data(
-                                                    |response_data| {
-                                                        response_data
-                                                            .content(format!(
-                                                                "Confession accepted by <@{}>",
-                                                                component.user.id
-                                                            ))
-                                                            .allowed_mentions(|mentions| {
-                                                                mentions.empty_parse()
-                                                            })
-                                                    },
-                                                )
-                                            })
-                                            .await
-                                        {
-                                            println!("Error sending message: {:?}", why);
-                                        } else {
-                                            valid = true;
-                                        }
-                                    }
-                                    None => {
-                                        valid = false;
-                                    }
-                                }
-                                valid
-                            }
-                            crate::button::ConfessionButton::DenyConfession => {
-                                let mut valid = false;
-                                if let Err(why) = component
-                                    .create_interaction_response(&ctx.http, |response| {
-                                        response.interaction_response_data(|response_data| {
-                                            response_data
-                                                .content(format!(
-                                                    "Confession denied by <@{}>",
-                                                    component.user.id
-                                                ))
-                                                .allowed_mentions(|mentions| mentions.empty_parse())
-                                        })
-                                    })
+                            crate::button::ConfessionButton::ApproveConfession(confession_id) => {
+                                vetting::approve_confession(ctx, component, data, confession_id)
                                     .await
-                                {
-                                    println!("Error sending message: {:?}", why);
-                                } else {
-                                    valid = true;
-                                }
-                                valid
+                            }
+                            crate::button::ConfessionButton::DenyConfession(confession_id) => {
+                                vetting::deny_confession(ctx, component, data, confession_id).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::DenyWithReason(confession_id) => {
+                                deny::select_deny_reason(ctx, component, data, confession_id).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::ClaimConfession(confession_id) => {
+                                vetting::claim_confession(ctx, component, data, confession_id)
+                                    .await;
+                                false
+                            }
+                            crate::button::ConfessionButton::EditConfession(confession_id) => {
+                                edit::open_edit_modal(ctx, component, data, confession_id).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::BlockAuthor(confession_id) => {
+                                blocks::open_block_menu(ctx, component, confession_id).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::BlockDuration(confession_id) => {
+                                blocks::select_block_duration(ctx, component, data, confession_id)
+                                    .await;
+                                false
+                            }
+                            crate::button::ConfessionButton::ContentWarning(confession_id) => {
+                                warnings::open_content_warning_modal(
+                                    ctx,
+                                    component,
+                                    data,
+                                    confession_id,
+                                )
+                                .await;
+                                false
+                            }
+                            crate::button::ConfessionButton::PollVote(poll_id, option_index) => {
+                                polls::cast_poll_vote(ctx, component, data, poll_id, option_index)
+                                    .await;
+                                false
+                            }
+                            crate::button::ConfessionButton::AnswerQuestion(confession_id) => {
+                                ask::answer_question(ctx, component, data, confession_id).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::SkipQuestion(confession_id) => {
+                                ask::skip_question(ctx, component, data, confession_id).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::PickQuestion => {
+                                ask::pick_question(ctx, component, data).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::ApproveDirect(direct_id) => {
+                                direct::approve_direct(ctx, component, data, direct_id).await
+                            }
+                            crate::button::ConfessionButton::DenyDirect(direct_id) => {
+                                direct::deny_direct(ctx, component, data, direct_id).await
+                            }
+                            crate::button::ConfessionButton::DirectReply(direct_id) => {
+                                direct::open_direct_reply_modal(ctx, component, data, direct_id)
+                                    .await;
+                                false
+                            }
+                            crate::button::ConfessionButton::DirectBlock(direct_id) => {
+                                direct::block_direct_sender(ctx, component, data, direct_id).await;
+                                false
+                            }
+                            crate::button::ConfessionButton::ModmailReply(modmail_thread_id) => {
+                                modmail::open_modmail_reply_modal(
+                                    ctx,
+                                    component,
+                                    data,
+                                    modmail_thread_id,
+                                )
+                                .await;
+                                false
+                            }
+                            crate::button::ConfessionButton::ReplyConfession(confession_id) => {
+                                replies::open_reply_modal(ctx, component, data, confession_id)
+                                    .await;
+                                false
+                            }
+                            crate::button::ConfessionButton::ApproveReply(reply_id) => {
+                                replies::approve_reply(ctx, component, data, reply_id).await
+                            }
+                            crate::button::ConfessionButton::DenyReply(reply_id) => {
+                                replies::deny_reply(ctx, component, data, reply_id).await
                             }
                             _ => false,
                         };
                         if should_clear {
                             let msg = component.message.clone();
-                            let mut edit = serenity::EditMessage::default();
-                            edit.set_components(serenity::CreateComponents::default());
-
-                            // Can't directly use `edit` fsr.
-                            if let Err(e) = ctx
-                                .http
-                                .edit_message_and_attachments(
-                                    msg.channel_id.0,
-                                    msg.id.0,
-                                    &::serenity::json::Value::from(
-                                        ::serenity::json::hashmap_to_json_map(edit.0),
-                                    ),
-                                    vec![],
-                                )
-                                .await
-                            {
-                                println!("Error sending message: {:?}", e);
-                            };
+                            clear_components(ctx, msg.channel_id, msg.id).await;
                         }
                     }
                     None => {}
@@ -726,7 +1218,16 @@ pub async fn shuffle(ctx: Context<'_>) -> Result<(), Error> {
     )
     .await
     {
-        Ok(_) => {
+        Ok(guild_confession) => {
+            if let Err(why) = pseudonyms::clear_stale_pseudonyms(
+                &ctx.data().database,
+                guild_confession.guild_id,
+                guild_confession.hash,
+            )
+            .await
+            {
+                println!("Error clearing pseudonyms: {:?}", why);
+            }
             ctx.say(format!("Shuffled!")).await?;
         }
         Err(e) => {
@@ -0,0 +1,347 @@
+use anyhow::anyhow;
+use poise::{serenity_prelude as serenity, Modal};
+
+use super::vetting;
+use crate::{
+    auth, button, crypto,
+    entity::modmail_threads,
+    operations::{self, channels::ChannelUse, guild_confessions},
+    util, Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+#[derive(Debug, Modal)]
+#[name = "Follow-up"]
+struct ModmailReplyModal {
+    #[name = "Message to the moderators"]
+    #[min_length = 1]
+    #[max_length = 1000]
+    #[paragraph]
+    content: String,
+}
+
+fn create_modmail_reply_button(modmail_thread_id: i32) -> serenity::CreateButton {
+    serenity::CreateButton::default()
+        .label("Reply anonymously")
+        .style(serenity::ButtonStyle::Secondary)
+        .custom_id(button::ConfessionButton::ModmailReply(modmail_thread_id).to_string())
+        .to_owned()
+}
+
+async fn post_to_thread(
+    ctx: &serenity::Context,
+    modmail_thread: &modmail_threads::Model,
+    content: &str,
+) -> anyhow::Result<()> {
+    serenity::ChannelId(modmail_thread.thread_id)
+        .send_message(ctx, |m| {
+            m.embed(|embed| {
+                embed
+                    .author(|a| a.name(format!("[{:x}]", modmail_thread.pseudonym)))
+                    .colour(modmail_thread.pseudonym)
+                    .description(content)
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+async fn send_to_reporter(
+    ctx: &serenity::Context,
+    data: &Data,
+    modmail_thread: &modmail_threads::Model,
+    title: String,
+    content: &str,
+) -> anyhow::Result<()> {
+    let author_id = crypto::decrypt_author(&data.author_key, &modmail_thread.author_ref)?;
+    let dm_channel = serenity::UserId(author_id).create_dm_channel(ctx).await?;
+    dm_channel
+        .send_message(ctx, |m| {
+            m.embed(|embed| embed.title(title).description(content))
+                .components(|components| {
+                    if modmail_thread.closed == 0 {
+                        components.create_action_row(|action_row| {
+                            action_row.add_button(create_modmail_reply_button(modmail_thread.id))
+                        });
+                    }
+                    components
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+// Each report gets its own private thread off the vetting channel, so only moderators can read it.
+pub async fn open_report(
+    ctx: &Context<'_>,
+    channel: serenity::ChannelId,
+    content: String,
+) -> anyhow::Result<String> {
+    let guild_id = ctx.guild_id().unwrap().0;
+    let vetting_channels = operations::channels::get_channels_in_guild_with_use(
+        &ctx.data().database,
+        guild_id,
+        ChannelUse::Vetting,
+    )
+    .await?;
+    let vetting_channel = match vetting_channels.first() {
+        Some(channel_model) => serenity::ChannelId(channel_model.id),
+        None => return Err(anyhow!("There is no vetting channel set.")),
+    };
+    // The same reporter keeps the same pseudonym across reports until the guild shuffles.
+    let epoch = guild_confessions::get_or_new_guild_confessions(&ctx.data().database, guild_id)
+        .await?
+        .hash;
+    let pseudonym = super::get_hash_from_user(epoch, ctx.author().id);
+    let thread = vetting_channel
+        .create_private_thread(ctx, |thread| {
+            thread
+                .name(format!("Report from [{:x}]", pseudonym))
+                .kind(serenity::ChannelType::PrivateThread)
+        })
+        .await?;
+    let modmail_thread = operations::modmail::add_modmail_thread(
+        &ctx.data().database,
+        guild_id,
+        crypto::encrypt_author(&ctx.data().author_key, ctx.author().id.0)?,
+        crypto::hash_author(&ctx.data().author_key, guild_id, ctx.author().id.0),
+        pseudonym,
+        thread.id.0,
+    )
+    .await?;
+    let admin_role = operations::guild::get_guild(&ctx.data().database, guild_id)
+        .await?
+        .and_then(|guild_model| guild_model.admin_role);
+    thread
+        .id
+        .send_message(ctx, |m| {
+            // Mentioning the role adds the moderators to the private thread.
+            let mut intro = format!(
+                "New anonymous report sent to <#{}>. Use `/modmail_reply` here to answer and `/modmail_close` when it's resolved.",
+                channel
+            );
+            if let Some(admin_role) = admin_role {
+                intro = format!("<@&{}> {}", admin_role, intro);
+                m.allowed_mentions(|mentions| mentions.roles(vec![admin_role]));
+            }
+            m.content(intro)
+        })
+        .await?;
+    post_to_thread(ctx.serenity_context(), &modmail_thread, &content).await?;
+    if let Err(why) = send_to_reporter(
+        ctx.serenity_context(),
+        ctx.data(),
+        &modmail_thread,
+        "Report sent".to_owned(),
+        &content,
+    )
+    .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+    Ok(
+        "Your report has been sent to the moderators anonymously. Their replies will come to your DMs."
+            .to_owned(),
+    )
+}
+
+pub async fn open_modmail_reply_modal(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    modmail_thread_id: i32,
+) {
+    let modmail_thread =
+        match operations::modmail::get_modmail_thread(&data.database, modmail_thread_id).await {
+            Ok(Some(modmail_thread)) => modmail_thread,
+            Ok(None) => {
+                vetting::respond_ephemeral(ctx, component, "Report not found".to_owned()).await;
+                return;
+            }
+            Err(why) => {
+                println!("Error getting modmail thread: {:?}", why);
+                return;
+            }
+        };
+    let author_hash = crypto::hash_author(
+        &data.author_key,
+        modmail_thread.guild_id,
+        component.user.id.0,
+    );
+    if author_hash != modmail_thread.author_hash || modmail_thread.closed == 1 {
+        vetting::respond_ephemeral(ctx, component, "This report has been closed.".to_owned()).await;
+        return;
+    }
+    let modal =
+        util::execute_component_modal::<ModmailReplyModal>(ctx, component, None, None).await;
+    let (modal_interaction, reply_modal) = match modal {
+        Ok(Some(submitted)) => submitted,
+        Ok(None) => return,
+        Err(why) => {
+            println!("Error getting modal: {:?}", why);
+            return;
+        }
+    };
+    let response = match post_to_thread(ctx, &modmail_thread, &reply_modal.content).await {
+        Ok(_) => "Your message has been sent to the moderators.".to_owned(),
+        Err(e) => format!(
+            "Error sending message: {}\nYour message has not been sent.",
+            e
+        ),
+    };
+    if let Err(why) = modal_interaction
+        .create_interaction_response(&ctx.http, |response_builder| {
+            response_builder.interaction_response_data(|response_data| {
+                response_data.content(response).ephemeral(true)
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+fn guild_name(ctx: &Context<'_>) -> String {
+    ctx.guild()
+        .map(|guild| guild.name)
+        .unwrap_or("the server".to_owned())
+}
+
+async fn get_current_modmail_thread(ctx: &Context<'_>) -> anyhow::Result<modmail_threads::Model> {
+    match operations::modmail::get_modmail_thread_by_channel(
+        &ctx.data().database,
+        ctx.channel_id().0,
+    )
+    .await?
+    {
+        Some(modmail_thread) if modmail_thread.closed == 1 => {
+            Err(anyhow!("This report has been closed."))
+        }
+        Some(modmail_thread) => Ok(modmail_thread),
+        None => Err(anyhow!("This isn't a modmail thread.")),
+    }
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn modmail_reply(
+    ctx: Context<'_>,
+    #[description = "Reply sent to the reporter"]
+    #[max_length = 1000]
+    content: String,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let sent = match get_current_modmail_thread(&ctx).await {
+        Ok(modmail_thread) => {
+            send_to_reporter(
+                ctx.serenity_context(),
+                ctx.data(),
+                &modmail_thread,
+                format!("Reply from the moderators of {}", guild_name(&ctx)),
+                &content,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    match sent {
+        Ok(_) => {
+            ctx.send(|builder| {
+                builder.embed(|embed| {
+                    embed
+                        .author(|a| a.name(&ctx.author().name))
+                        .description(&content)
+                        .footer(|f| f.text("Sent to the reporter"))
+                })
+            })
+            .await?;
+        }
+        Err(e) => {
+            ctx.send(|builder| {
+                builder
+                    .content(format!("Error sending reply: {}", e))
+                    .ephemeral(true)
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn modmail_close(ctx: Context<'_>) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let modmail_thread = match get_current_modmail_thread(&ctx).await {
+        Ok(modmail_thread) => modmail_thread,
+        Err(e) => {
+            ctx.send(|builder| builder.content(e.to_string()).ephemeral(true))
+                .await?;
+            return Ok(());
+        }
+    };
+    if !operations::modmail::close_modmail_thread(&ctx.data().database, modmail_thread.id).await? {
+        ctx.send(|builder| {
+            builder
+                .content("This report has already been closed.")
+                .ephemeral(true)
+        })
+        .await?;
+        return Ok(());
+    }
+    let closed_thread = modmail_threads::Model {
+        closed: 1,
+        ..modmail_thread
+    };
+    if let Err(why) = send_to_reporter(
+        ctx.serenity_context(),
+        ctx.data(),
+        &closed_thread,
+        format!("Your report to {} has been closed", guild_name(&ctx)),
+        "The moderators have marked your report as resolved. Send a new one if you need to.",
+    )
+    .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+    ctx.say(format!("Report closed by <@{}>.", ctx.author().id))
+        .await?;
+    if let Err(why) = ctx
+        .channel_id()
+        .edit_thread(ctx, |thread| thread.archived(true).locked(true))
+        .await
+    {
+        println!("Error archiving thread: {:?}", why);
+    }
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_modmail(ctx: Context<'_>) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    super::super::channel::set_channel(&ctx, ChannelUse::Modmail).await
+}
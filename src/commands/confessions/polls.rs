@@ -0,0 +1,359 @@
+use std::time::Duration;
+
+use poise::serenity_prelude as serenity;
+
+use super::{replies, vetting};
+use crate::{
+    button, crypto,
+    entity::{confession_polls, guild_filter_rules, poll_votes},
+    filter::{self, FilterOutcome},
+    operations, Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+pub const RESULTS_FIELD: &str = "Results";
+const DEFAULT_POLL_HOURS: u32 = 24;
+const MAX_POLL_HOURS: u32 = 7 * 24;
+const POLL_CHECK_SECONDS: u64 = 60;
+const OPTIONS_PER_ROW: usize = 5;
+const RESULT_BAR_LENGTH: usize = 10;
+// Discord's limit for button labels, which redaction can push an option past.
+const MAX_LABEL_LENGTH: usize = 80;
+
+#[derive(Debug, Clone)]
+pub struct PollDraft {
+    pub options: Vec<String>,
+    pub duration_hours: u32,
+}
+
+pub fn describe_options(poll: &PollDraft) -> String {
+    let mut description = poll
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| format!("{}. {}", i + 1, option))
+        .collect::<Vec<String>>()
+        .join("\n");
+    description.push_str(&format!(
+        "\nCloses {} hour(s) after posting",
+        poll.duration_hours
+    ));
+    description
+}
+
+// Options are posted as button labels, so they're held to the same filters as the question.
+pub fn filter_options(
+    rules: &[guild_filter_rules::Model],
+    poll: &mut PollDraft,
+    outcome: &mut FilterOutcome,
+) {
+    for option in poll.options.iter_mut() {
+        let filtered = filter::apply_filters(rules, option);
+        *option = filtered.content.chars().take(MAX_LABEL_LENGTH).collect();
+        for flag in filtered.flags {
+            if !outcome.flags.contains(&flag) {
+                outcome.flags.push(flag);
+            }
+        }
+        if outcome.rejection.is_none() {
+            outcome.rejection = filtered.rejection;
+        }
+    }
+}
+
+pub fn results_text(
+    poll: &confession_polls::Model,
+    options: &[String],
+    votes: &[poll_votes::Model],
+) -> String {
+    let total = votes.len();
+    let mut lines = options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let count = votes
+                .iter()
+                .filter(|vote| vote.option_index as usize == i)
+                .count();
+            let percent = match total {
+                0 => 0,
+                total => count * 100 / total,
+            };
+            let filled = percent * RESULT_BAR_LENGTH / 100;
+            format!(
+                "**{}**\n`{}{}` {} ({}%)",
+                option,
+                "█".repeat(filled),
+                "░".repeat(RESULT_BAR_LENGTH - filled),
+                count,
+                percent
+            )
+        })
+        .collect::<Vec<String>>();
+    lines.push(match (poll.closed == 1, poll.closes_at) {
+        (false, Some(closes_at)) => {
+            format!("{} vote(s) · closes <t:{}:R>", total, closes_at.timestamp())
+        }
+        _ => format!("{} vote(s) · poll closed", total),
+    });
+    lines.join("\n")
+}
+
+pub fn add_poll_buttons<'a>(
+    components: &'a mut serenity::CreateComponents,
+    poll_id: i32,
+    options: &[String],
+) -> &'a mut serenity::CreateComponents {
+    for (row, row_options) in options.chunks(OPTIONS_PER_ROW).enumerate() {
+        components.create_action_row(|action_row| {
+            for (i, option) in row_options.iter().enumerate() {
+                let option_index = (row * OPTIONS_PER_ROW + i) as u32;
+                action_row.add_button(
+                    serenity::CreateButton::default()
+                        .label(option)
+                        .style(serenity::ButtonStyle::Primary)
+                        .custom_id(
+                            button::ConfessionButton::PollVote(poll_id, option_index).to_string(),
+                        )
+                        .to_owned(),
+                );
+            }
+            action_row
+        });
+    }
+    components
+}
+
+fn with_results(embed: serenity::Embed, results: String) -> serenity::CreateEmbed {
+    let mut embed = embed;
+    embed.fields.retain(|field| field.name != RESULTS_FIELD);
+    let mut results_embed = serenity::CreateEmbed::from(embed);
+    results_embed.field(RESULTS_FIELD, results, false);
+    results_embed
+}
+
+pub async fn cast_poll_vote(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    poll_id: i32,
+    option_index: u32,
+) {
+    let poll = match operations::polls::get_poll(&data.database, poll_id).await {
+        Ok(Some(poll)) => poll,
+        Ok(None) => {
+            vetting::respond_ephemeral(ctx, component, "Poll not found".to_owned()).await;
+            return;
+        }
+        Err(why) => {
+            println!("Error getting poll: {:?}", why);
+            return;
+        }
+    };
+    let is_open = poll.closed == 0
+        && poll
+            .closes_at
+            .map(|closes_at| closes_at > chrono::Utc::now())
+            .unwrap_or(false);
+    let options = operations::polls::get_poll_options(&poll);
+    if !is_open || option_index as usize >= options.len() {
+        vetting::respond_ephemeral(ctx, component, "This poll is closed.".to_owned()).await;
+        return;
+    }
+    let voter_hash = crypto::hash_poll_voter(&data.author_key, poll.id, component.user.id.0);
+    let votes =
+        match operations::polls::set_poll_vote(&data.database, poll.id, voter_hash, option_index)
+            .await
+        {
+            Ok(_) => operations::polls::get_poll_votes(&data.database, poll.id).await,
+            Err(e) => Err(e),
+        };
+    let votes = match votes {
+        Ok(votes) => votes,
+        Err(e) => {
+            vetting::respond_ephemeral(ctx, component, format!("Error voting: {}", e)).await;
+            return;
+        }
+    };
+    let results = results_text(&poll, &options, &votes);
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|response_data| {
+                    if let Some(embed) = component.message.embeds.first().cloned() {
+                        response_data.set_embed(with_results(embed, results));
+                    }
+                    response_data
+                })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+        return;
+    }
+    if let Err(why) = component
+        .create_followup_message(&ctx.http, |followup| {
+            followup
+                .content(format!(
+                    "Your vote for **{}** has been counted.",
+                    options[option_index as usize]
+                ))
+                .ephemeral(true)
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+async fn close_poll(
+    ctx: &serenity::Context,
+    data: &Data,
+    poll: confession_polls::Model,
+) -> anyhow::Result<()> {
+    if !operations::polls::close_poll(&data.database, poll.id).await? {
+        return Ok(());
+    }
+    let confession =
+        match operations::confessions::get_confession(&data.database, poll.confession_id).await? {
+            Some(confession) => confession,
+            None => return Ok(()),
+        };
+    let posted_message_id = match confession.posted_message_id {
+        Some(posted_message_id) => serenity::MessageId(posted_message_id),
+        None => return Ok(()),
+    };
+    let channel_id = serenity::ChannelId(confession.channel_id);
+    let message = channel_id.message(ctx, posted_message_id).await?;
+    let options = operations::polls::get_poll_options(&poll);
+    let votes = operations::polls::get_poll_votes(&data.database, poll.id).await?;
+    let closed_poll = confession_polls::Model { closed: 1, ..poll };
+    let results = results_text(&closed_poll, &options, &votes);
+    channel_id
+        .edit_message(ctx, posted_message_id, |m| {
+            if let Some(embed) = message.embeds.first().cloned() {
+                m.set_embed(with_results(embed, results));
+            }
+            m.components(|components| {
+                components.create_action_row(|action_row| {
+                    action_row.add_button(replies::create_reply_button(confession.id))
+                })
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+async fn close_expired_polls(ctx: &serenity::Context, data: &Data) -> anyhow::Result<()> {
+    for poll in operations::polls::get_expired_polls(&data.database, chrono::Utc::now()).await? {
+        if let Err(why) = close_poll(ctx, data, poll).await {
+            println!("Error closing poll: {:?}", why);
+        }
+    }
+    Ok(())
+}
+
+// Deadlines are stored with the poll, so polls that ran out while the bot was down close on the first tick.
+pub async fn close_polls(ctx: serenity::Context, data: Data) {
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_CHECK_SECONDS));
+    loop {
+        interval.tick().await;
+        if let Err(why) = close_expired_polls(&ctx, &data).await {
+            println!("Error closing polls: {:?}", why);
+        }
+    }
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    description_localized("en-GB", "Posts an anonymous poll to the current channel."),
+    description_localized("en-US", "Posts an anonymous poll to the current channel."),
+    guild_only = true
+)]
+// One parameter per option, as Discord has no variadic command options.
+#[allow(clippy::too_many_arguments)]
+pub async fn confess_poll(
+    ctx: Context<'_>,
+    #[description = "Question"]
+    #[max_length = 500]
+    question: String,
+    #[description = "First option"]
+    #[max_length = 50]
+    option_1: String,
+    #[description = "Second option"]
+    #[max_length = 50]
+    option_2: String,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_3: Option<String>,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_4: Option<String>,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_5: Option<String>,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_6: Option<String>,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_7: Option<String>,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_8: Option<String>,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_9: Option<String>,
+    #[description = "Another option"]
+    #[max_length = 50]
+    option_10: Option<String>,
+    #[description = "Hours until the poll closes (default 24)"]
+    #[min = 1]
+    #[max = 168]
+    hours: Option<u32>,
+) -> Result<(), Error> {
+    let options = [
+        Some(option_1),
+        Some(option_2),
+        option_3,
+        option_4,
+        option_5,
+        option_6,
+        option_7,
+        option_8,
+        option_9,
+        option_10,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|option| option.trim().to_owned())
+    .filter(|option| !option.is_empty())
+    .collect::<Vec<String>>();
+    if options.len() < 2 {
+        ctx.send(|builder| {
+            builder
+                .content("A poll needs at least two options.")
+                .ephemeral(true)
+        })
+        .await?;
+        return Ok(());
+    }
+    let poll = PollDraft {
+        options,
+        duration_hours: hours.unwrap_or(DEFAULT_POLL_HOURS).clamp(1, MAX_POLL_HOURS),
+    };
+    super::_confess_to(
+        &ctx,
+        ctx.channel_id(),
+        super::ConfessionInput {
+            content: Some(question),
+            poll: Some(poll),
+            ..Default::default()
+        },
+    )
+    .await
+}
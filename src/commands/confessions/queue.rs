@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use poise::serenity_prelude as serenity;
+
+use crate::{
+    auth,
+    entity::confessions,
+    operations::{self, channels::ChannelUse},
+    Data,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+const MAX_QUEUE_LINES: usize = 15;
+const EXPIRY_CHECK_MINUTES: u64 = 10;
+
+fn queue_line(
+    guild_id: u64,
+    vetting_channel: Option<u64>,
+    confession: &confessions::Model,
+) -> String {
+    let mut line = format!(
+        "- #{} to <#{}> from [{:x}], sent <t:{}:R>",
+        confession.number,
+        confession.channel_id,
+        confession.pseudonym,
+        confession.created_at.timestamp()
+    );
+    if let (Some(channel_id), Some(message_id)) = (vetting_channel, confession.vetting_message_id) {
+        line.push_str(&format!(
+            " · [vetting message](https://discord.com/channels/{}/{}/{})",
+            guild_id, channel_id, message_id
+        ));
+    }
+    line
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn queue(ctx: Context<'_>) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let pending = operations::confessions::get_pending_confessions(&db, this_guild, None).await?;
+    let scheduled =
+        operations::scheduled_posts::count_guild_scheduled_posts(&db, this_guild).await?;
+    let scheduled_line = match scheduled {
+        0 => "".to_owned(),
+        n => format!("\n{} approved confession(s) waiting to be posted.", n),
+    };
+    if pending.is_empty() {
+        ctx.say(format!(
+            "There are no confessions waiting to be vetted.{}",
+            scheduled_line
+        ))
+        .await?;
+        return Ok(());
+    }
+    let vetting_channel =
+        operations::channels::get_channels_in_guild_with_use(&db, this_guild, ChannelUse::Vetting)
+            .await?
+            .first()
+            .map(|channel| channel.id);
+    let mut lines = pending
+        .iter()
+        .take(MAX_QUEUE_LINES)
+        .map(|confession| queue_line(this_guild, vetting_channel, confession))
+        .collect::<Vec<String>>();
+    if pending.len() > MAX_QUEUE_LINES {
+        lines.push(format!("…and {} more", pending.len() - MAX_QUEUE_LINES));
+    }
+    ctx.send(|builder| {
+        builder
+            .content(format!(
+                "{} confession(s) waiting to be vetted:\n{}{}",
+                pending.len(),
+                lines.join("\n"),
+                scheduled_line
+            ))
+            .ephemeral(true)
+            .allowed_mentions(|mentions| mentions.empty_parse())
+    })
+    .await?;
+    Ok(())
+}
+
+async fn expire_guild_confessions(
+    ctx: &serenity::Context,
+    bot: serenity::UserId,
+    data: &Data,
+) -> anyhow::Result<()> {
+    for guild in operations::guild::get_guilds_with_pending_expiry(&data.database).await? {
+        let before =
+            chrono::Utc::now() - chrono::Duration::hours(guild.pending_expiry_hours as i64);
+        let expired = operations::confessions::get_pending_confessions(
+            &data.database,
+            guild.id,
+            Some(before),
+        )
+        .await?;
+        if expired.is_empty() {
+            continue;
+        }
+        let vetting_channel = match operations::channels::get_channels_in_guild_with_use(
+            &data.database,
+            guild.id,
+            ChannelUse::Vetting,
+        )
+        .await?
+        .first()
+        {
+            Some(channel_model) => serenity::ChannelId(channel_model.id),
+            None => continue,
+        };
+        for confession in expired {
+            if let Err(why) = super::deny::finish_deny(
+                ctx,
+                data,
+                vetting_channel,
+                bot,
+                confession.id,
+                Some(format!(
+                    "Not reviewed within {} hour(s).",
+                    guild.pending_expiry_hours
+                )),
+            )
+            .await
+            {
+                println!("Error expiring confession: {:?}", why);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Runs for the lifetime of the bot, denying confessions left pending past their guild's expiry.
+pub async fn expire_pending_confessions(ctx: serenity::Context, bot: serenity::UserId, data: Data) {
+    let mut interval = tokio::time::interval(Duration::from_secs(EXPIRY_CHECK_MINUTES * 60));
+    loop {
+        interval.tick().await;
+        if let Err(why) = expire_guild_confessions(&ctx, bot, &data).await {
+            println!("Error expiring confessions: {:?}", why);
+        }
+    }
+}
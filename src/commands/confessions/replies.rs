@@ -0,0 +1,430 @@
+use anyhow::anyhow;
+use poise::{serenity_prelude as serenity, Modal};
+
+use super::{blocks, limits, vetting};
+use crate::{
+    button, crypto,
+    entity::{confession_replies, confessions},
+    filter,
+    operations::{self, channels::ChannelUse, confessions::ConfessionStatus},
+    util, Data,
+};
+
+pub const OP_PSEUDONYM: &str = "OP";
+
+#[derive(Debug, Modal)]
+#[name = "Reply"]
+struct ReplyModal {
+    #[name = "Reply content"]
+    #[min_length = 1]
+    #[max_length = 500]
+    #[paragraph]
+    content: String,
+}
+
+// 0 -> "Anon A", 25 -> "Anon Z", 26 -> "Anon AA"
+fn reply_label(index: usize) -> String {
+    let mut n = index + 1;
+    let mut label = String::new();
+    while n > 0 {
+        n -= 1;
+        label.insert(0, (b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    format!("Anon {}", label)
+}
+
+pub const MAX_REPLY_PSEUDONYM_ATTEMPTS: u32 = 8;
+
+// Labels are handed out in order, replies from before labels were stored keep theirs.
+pub async fn get_reply_pseudonym(
+    db: &sea_orm::DatabaseConnection,
+    key: &crypto::AuthorKey,
+    confession: &confessions::Model,
+    user: serenity::UserId,
+) -> anyhow::Result<(String, String)> {
+    let author_hash = crypto::hash_replier(key, confession.id, user.0);
+    if let Ok(author_id) = crypto::decrypt_author(key, &confession.author_ref) {
+        if author_id == user.0 {
+            return Ok((author_hash, OP_PSEUDONYM.to_owned()));
+        }
+    }
+    for _ in 0..MAX_REPLY_PSEUDONYM_ATTEMPTS {
+        let labels = operations::replies::get_reply_pseudonyms(db, confession.id).await?;
+        if let Some(label) = labels.iter().find(|l| l.author_hash == author_hash) {
+            return Ok((author_hash, label.pseudonym.clone()));
+        }
+        let replies = operations::replies::get_replies_to_confession(db, confession.id).await?;
+        if let Some(reply) = replies.iter().find(|r| r.author_hash == author_hash) {
+            return Ok((author_hash, reply.pseudonym.clone()));
+        }
+        let taken = labels
+            .iter()
+            .map(|l| &l.pseudonym)
+            .chain(replies.iter().map(|r| &r.pseudonym))
+            .collect::<Vec<&String>>();
+        let candidate = (0..)
+            .map(reply_label)
+            .find(|label| !taken.contains(&label))
+            .unwrap_or_default();
+        if operations::replies::add_reply_pseudonym(
+            db,
+            confession.id,
+            author_hash.clone(),
+            candidate.clone(),
+        )
+        .await
+        .is_ok()
+        {
+            return Ok((author_hash, candidate));
+        }
+    }
+    Err(anyhow!("Could not find a free reply label, try again."))
+}
+
+pub fn create_reply_button(confession_id: i32) -> serenity::CreateButton {
+    serenity::CreateButton::default()
+        .label("Reply anonymously")
+        .style(serenity::ButtonStyle::Secondary)
+        .custom_id(button::ConfessionButton::ReplyConfession(confession_id).to_string())
+        .to_owned()
+}
+
+async fn get_or_create_thread(
+    ctx: &serenity::Context,
+    confession: &confessions::Model,
+) -> anyhow::Result<serenity::ChannelId> {
+    let posted_message_id = match confession.posted_message_id {
+        Some(id) => id,
+        None => return Err(anyhow!("Confession has not been posted")),
+    };
+    // Threads started from a message share that message's ID.
+    let thread_id = serenity::ChannelId(posted_message_id);
+    if thread_id.to_channel(ctx).await.is_ok() {
+        return Ok(thread_id);
+    }
+    match serenity::ChannelId(confession.channel_id)
+        .create_public_thread(ctx, posted_message_id, |thread| {
+            thread.name(format!("Confession #{} replies", confession.number))
+        })
+        .await
+    {
+        Ok(thread) => Ok(thread.id),
+        Err(e) => Err(anyhow!("Error creating thread: {}", e.to_string())),
+    }
+}
+
+pub async fn post_reply(
+    ctx: &serenity::Context,
+    data: &Data,
+    confession: &confessions::Model,
+    mut reply: confession_replies::Model,
+) -> anyhow::Result<confession_replies::Model> {
+    let thread_id = get_or_create_thread(ctx, confession).await?;
+    let posted = thread_id
+        .send_message(ctx, |m| {
+            m.embed(|embed| {
+                embed
+                    .description(&reply.content)
+                    .author(|a| a.name(&reply.pseudonym))
+            })
+        })
+        .await;
+    match posted {
+        Ok(message) => {
+            reply.posted_message_id = Some(message.id.0);
+            reply.status = ConfessionStatus::Approved.into();
+            operations::replies::set_reply(&data.database, reply).await
+        }
+        Err(e) => Err(anyhow!("Error sending message: {}", e.to_string())),
+    }
+}
+
+async fn send_verify_reply(
+    ctx: &serenity::Context,
+    data: &Data,
+    confession: &confessions::Model,
+    mut reply: confession_replies::Model,
+    flags: Vec<String>,
+) -> anyhow::Result<()> {
+    let vetting_channels = operations::channels::get_channels_in_guild_with_use(
+        &data.database,
+        confession.guild_id,
+        ChannelUse::Vetting,
+    )
+    .await?;
+    let channel_id = match vetting_channels.first() {
+        Some(channel_model) => serenity::ChannelId(channel_model.id),
+        None => return Err(anyhow!("There is no vetting channel set.")),
+    };
+    let sent = channel_id
+        .send_message(ctx, |m| {
+            m.embed(|embed| {
+                embed
+                    .title(format!("Reply to Confession #{}", confession.number))
+                    .description(&reply.content)
+                    .author(|a| a.name(&reply.pseudonym));
+                if !flags.is_empty() {
+                    embed.field("Flags", flags.join("\n"), false);
+                }
+                embed
+            })
+            .content(format!("Reply going to <#{}>", confession.channel_id))
+            .components(|components| {
+                components.create_action_row(|action_row| {
+                    action_row
+                        .add_button(
+                            serenity::CreateButton::default()
+                                .label("Approve")
+                                .style(serenity::ButtonStyle::Success)
+                                .custom_id(
+                                    button::ConfessionButton::ApproveReply(reply.id).to_string(),
+                                )
+                                .to_owned(),
+                        )
+                        .add_button(
+                            serenity::CreateButton::default()
+                                .label("Deny")
+                                .style(serenity::ButtonStyle::Danger)
+                                .custom_id(
+                                    button::ConfessionButton::DenyReply(reply.id).to_string(),
+                                )
+                                .to_owned(),
+                        )
+                })
+            })
+        })
+        .await;
+    match sent {
+        Ok(message) => {
+            reply.vetting_message_id = Some(message.id.0);
+            operations::replies::set_reply(&data.database, reply).await?;
+            Ok(())
+        }
+        Err(e) => Err(anyhow!("Error sending message: {}", e.to_string())),
+    }
+}
+
+async fn submit_reply(
+    ctx: &serenity::Context,
+    data: &Data,
+    confession: &confessions::Model,
+    user: serenity::UserId,
+    content: String,
+) -> anyhow::Result<String> {
+    // Reply hashes are per confession, so the block is looked up by the member's server hash.
+    let guild_author_hash = crypto::hash_author(&data.author_key, confession.guild_id, user.0);
+    if let Some(refusal) =
+        blocks::check_blocked(data, confession.guild_id, &guild_author_hash).await?
+    {
+        return Ok(refusal);
+    }
+    if let Some(refusal) =
+        limits::check_reply_rate_limits(data, confession.guild_id, &guild_author_hash).await?
+    {
+        return Ok(refusal);
+    }
+    let rules = operations::filters::get_guild_filters(&data.database, confession.guild_id).await?;
+    let filtered = filter::apply_filters(&rules, &content);
+    if let Some(rejection) = filtered.rejection {
+        return Ok(format!("Your reply was rejected because {}.", rejection));
+    }
+    let (author_hash, pseudonym) =
+        get_reply_pseudonym(&data.database, &data.author_key, confession, user).await?;
+    let reply = operations::replies::add_reply(
+        &data.database,
+        confession.id,
+        author_hash,
+        guild_author_hash,
+        pseudonym,
+        filtered.content,
+    )
+    .await?;
+    // Anything a filter flagged goes past a moderator even when replies aren't vetted.
+    if !filtered.flags.is_empty()
+        || operations::guild::get_guild_vet_replies(&data.database, confession.guild_id).await?
+    {
+        send_verify_reply(ctx, data, confession, reply, filtered.flags).await?;
+        Ok("Your reply has been sent to be vetted.".to_owned())
+    } else {
+        post_reply(ctx, data, confession, reply).await?;
+        Ok("Your reply has been posted.".to_owned())
+    }
+}
+
+pub async fn open_reply_modal(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let confession =
+        match operations::confessions::get_confession(&data.database, confession_id).await {
+            Ok(Some(confession))
+                if ConfessionStatus::from(confession.status) == ConfessionStatus::Approved =>
+            {
+                confession
+            }
+            Ok(_) => {
+                if let Err(why) = component
+                    .create_interaction_response(&ctx.http, |response| {
+                        response.interaction_response_data(|response_data| {
+                            response_data
+                                .content("This confession can't be replied to.")
+                                .ephemeral(true)
+                        })
+                    })
+                    .await
+                {
+                    println!("Error sending message: {:?}", why);
+                }
+                return;
+            }
+            Err(why) => {
+                println!("Error getting confession: {:?}", why);
+                return;
+            }
+        };
+    let modal = util::execute_component_modal::<ReplyModal>(ctx, component, None, None).await;
+    let (modal_interaction, reply_modal) = match modal {
+        Ok(Some(submitted)) => submitted,
+        Ok(None) => return,
+        Err(why) => {
+            println!("Error getting modal: {:?}", why);
+            return;
+        }
+    };
+    let response = match submit_reply(
+        ctx,
+        data,
+        &confession,
+        component.user.id,
+        reply_modal.content,
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(e) => format!(
+            "Error sending reply: {}\nYour reply has not been processed.",
+            e
+        ),
+    };
+    if let Err(why) = modal_interaction
+        .create_interaction_response(&ctx.http, |response_builder| {
+            response_builder.interaction_response_data(|response_data| {
+                response_data.content(response).ephemeral(true)
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+async fn already_resolved(data: &Data, reply_id: i32) -> String {
+    match operations::replies::get_reply(&data.database, reply_id).await {
+        Ok(Some(reply)) => format!(
+            "This reply has already been {}.",
+            ConfessionStatus::from(reply.status)
+        ),
+        _ => "Reply not found".to_owned(),
+    }
+}
+
+pub async fn approve_reply(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    reply_id: i32,
+) -> bool {
+    let reply = match operations::replies::get_reply(&data.database, reply_id).await {
+        Ok(Some(reply)) => reply,
+        Ok(None) => return false,
+        Err(why) => {
+            println!("Error getting reply: {:?}", why);
+            return false;
+        }
+    };
+    let confession =
+        match operations::confessions::get_confession(&data.database, reply.confession_id).await {
+            Ok(Some(confession)) => confession,
+            Ok(None) => return false,
+            Err(why) => {
+                println!("Error getting confession: {:?}", why);
+                return false;
+            }
+        };
+    match operations::replies::resolve_reply(&data.database, reply_id, ConfessionStatus::Approved)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            vetting::respond_ephemeral(ctx, component, already_resolved(data, reply_id).await)
+                .await;
+            return false;
+        }
+        Err(why) => {
+            vetting::respond_ephemeral(ctx, component, why.to_string()).await;
+            return false;
+        }
+    }
+    if let Err(why) = post_reply(ctx, data, &confession, reply.clone()).await {
+        println!("Error posting reply: {:?}", why);
+        // Put it back up for review so it isn't stuck as approved without being posted.
+        if let Err(why) = operations::replies::set_reply(&data.database, reply).await {
+            println!("Error saving reply: {:?}", why);
+        }
+        vetting::respond_ephemeral(ctx, component, format!("Error posting reply: {}", why)).await;
+        return false;
+    }
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data
+                    .content(format!("Reply accepted by <@{}>", component.user.id))
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+        return false;
+    }
+    true
+}
+
+pub async fn deny_reply(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    reply_id: i32,
+) -> bool {
+    match operations::replies::resolve_reply(&data.database, reply_id, ConfessionStatus::Denied)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            vetting::respond_ephemeral(ctx, component, already_resolved(data, reply_id).await)
+                .await;
+            return false;
+        }
+        Err(why) => {
+            vetting::respond_ephemeral(ctx, component, why.to_string()).await;
+            return false;
+        }
+    }
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data
+                    .content(format!("Reply denied by <@{}>", component.user.id))
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+        return false;
+    }
+    true
+}
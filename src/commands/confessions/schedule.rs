@@ -0,0 +1,273 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::TimeZone;
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+
+use super::vetting;
+use crate::{
+    entity::{confessions, guild, scheduled_posts},
+    operations::{self, channels::ChannelUse, confessions::ConfessionStatus},
+    Data,
+};
+
+const SCHEDULE_CHECK_SECONDS: u64 = 60;
+pub const MAX_SCHEDULE_MINUTES: u32 = 24 * 60;
+
+// None when the guild posts approved confessions straight away.
+pub fn next_post_time(
+    guild: &guild::Model,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    if guild.post_delay_minutes == 0
+        && guild.post_jitter_minutes == 0
+        && guild.post_batch_minutes == 0
+    {
+        return None;
+    }
+    let jitter = rand::thread_rng().gen_range(0..=guild.post_jitter_minutes as i64 * 60);
+    let post_at = now
+        + chrono::Duration::minutes(guild.post_delay_minutes as i64)
+        + chrono::Duration::seconds(jitter);
+    if guild.post_batch_minutes == 0 {
+        return Some(post_at);
+    }
+    // Batches line up with the clock, so hourly batches post on the hour.
+    let batch = guild.post_batch_minutes as i64 * 60;
+    let batch_start = (post_at.timestamp() + batch - 1) / batch * batch;
+    chrono::Utc.timestamp_opt(batch_start, 0).single()
+}
+
+pub fn describe_schedule(guild: &guild::Model) -> String {
+    if guild.post_delay_minutes == 0
+        && guild.post_jitter_minutes == 0
+        && guild.post_batch_minutes == 0
+    {
+        return "Approved confessions are posted straight away.".to_owned();
+    }
+    let mut schedule = format!(
+        "Approved confessions are posted after {} minute(s), plus up to {} minute(s) at random",
+        guild.post_delay_minutes, guild.post_jitter_minutes
+    );
+    if guild.post_batch_minutes > 0 {
+        schedule.push_str(&format!(
+            ", in batches every {} minute(s)",
+            guild.post_batch_minutes
+        ));
+    }
+    schedule.push('.');
+    schedule
+}
+
+// Returns when the confession will be posted if it was queued rather than posted now.
+pub async fn post_or_schedule(
+    ctx: &serenity::Context,
+    data: &Data,
+    confession: confessions::Model,
+) -> anyhow::Result<Option<chrono::DateTime<chrono::Utc>>> {
+    // Questions are answered by the channel's host before anything is posted.
+    if super::ask::is_ask_me(data, &confession).await? {
+        super::ask::deliver_question(ctx, data, &confession).await?;
+        return Ok(None);
+    }
+    let post_at = match operations::guild::get_guild(&data.database, confession.guild_id).await? {
+        Some(guild_model) => next_post_time(&guild_model, chrono::Utc::now()),
+        None => None,
+    };
+    match post_at {
+        Some(post_at) => {
+            operations::scheduled_posts::add_scheduled_post(
+                &data.database,
+                confession.guild_id,
+                confession.id,
+                post_at,
+            )
+            .await?;
+            Ok(Some(post_at))
+        }
+        None => {
+            super::post_confession(ctx, data, confession).await?;
+            Ok(None)
+        }
+    }
+}
+
+// Sends a failed post back to the vetting channel so it isn't lost as approved but never posted.
+pub async fn return_to_vetting(
+    ctx: &serenity::Context,
+    data: &Data,
+    mut confession: confessions::Model,
+    why: anyhow::Error,
+) -> anyhow::Result<()> {
+    confession.status = ConfessionStatus::Pending.into();
+    let confession = operations::confessions::set_confession(&data.database, confession).await?;
+    let vetting_channels = operations::channels::get_channels_in_guild_with_use(
+        &data.database,
+        confession.guild_id,
+        ChannelUse::Vetting,
+    )
+    .await?;
+    if let Some(channel_model) = vetting_channels.first() {
+        let channel_id = serenity::ChannelId(channel_model.id);
+        channel_id
+            .send_message(ctx, |m| {
+                if let Some(vetting_message_id) = confession.vetting_message_id {
+                    m.reference_message((channel_id, serenity::MessageId(vetting_message_id)));
+                }
+                m.content(format!(
+                    "Confession #{} could not be posted and is waiting for review again: {}",
+                    confession.number, why
+                ))
+                .components(|components| vetting::add_vetting_buttons(components, confession.id))
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+// Only approved confessions leave the queue, so ones that skip vetting are approved before queueing.
+// Returns true if posting failed and the confession went back to the vetting channel.
+pub async fn post_unvetted(
+    ctx: &serenity::Context,
+    data: &Data,
+    mut confession: confessions::Model,
+) -> anyhow::Result<bool> {
+    if !operations::confessions::approve_unvetted_confession(&data.database, confession.id).await? {
+        return Err(anyhow!(
+            "Confession #{} is no longer pending.",
+            confession.number
+        ));
+    }
+    confession.status = ConfessionStatus::Approved.into();
+    match post_or_schedule(ctx, data, confession.clone()).await {
+        Ok(_) => Ok(false),
+        Err(why) => {
+            println!("Error posting confession: {:?}", why);
+            return_to_vetting(ctx, data, confession, why).await?;
+            Ok(true)
+        }
+    }
+}
+
+async fn post_scheduled_confession(
+    ctx: &serenity::Context,
+    data: &Data,
+    scheduled_post: &scheduled_posts::Model,
+) -> anyhow::Result<()> {
+    let confession =
+        operations::confessions::get_confession(&data.database, scheduled_post.confession_id)
+            .await?;
+    if let Some(confession) = confession {
+        // Skip anything that was already posted or taken back while it waited.
+        if ConfessionStatus::from(confession.status) == ConfessionStatus::Approved
+            && confession.posted_message_id.is_none()
+        {
+            if let Err(why) = super::post_confession(ctx, data, confession.clone()).await {
+                println!("Error posting confession: {:?}", why);
+                return_to_vetting(ctx, data, confession, why).await?;
+            }
+        }
+    }
+    operations::scheduled_posts::remove_scheduled_post(&data.database, scheduled_post.id).await
+}
+
+async fn post_due_confessions(ctx: &serenity::Context, data: &Data) -> anyhow::Result<()> {
+    let due =
+        operations::scheduled_posts::get_due_scheduled_posts(&data.database, chrono::Utc::now())
+            .await?;
+    for scheduled_post in due {
+        if let Err(why) = post_scheduled_confession(ctx, data, &scheduled_post).await {
+            println!("Error posting scheduled confession: {:?}", why);
+        }
+    }
+    Ok(())
+}
+
+// The queue lives in the database, so anything still waiting after a restart is posted on the first tick.
+pub async fn post_scheduled_confessions(ctx: serenity::Context, data: Data) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULE_CHECK_SECONDS));
+    loop {
+        interval.tick().await;
+        if let Err(why) = post_due_confessions(&ctx, &data).await {
+            println!("Error posting scheduled confessions: {:?}", why);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guild_with_schedule(delay: u32, jitter: u32, batch: u32) -> guild::Model {
+        guild::Model {
+            id: 1,
+            admin_role: None,
+            vet_replies: 0,
+            required_approvals: 1,
+            required_denials: 1,
+            pending_expiry_hours: 0,
+            trusted_role: None,
+            trusted_after: 0,
+            duplicate_action: 0,
+            max_attachments: 1,
+            max_attachment_mb: 8,
+            allow_video: 0,
+            allow_audio: 0,
+            allowed_file_types: None,
+            post_delay_minutes: delay,
+            post_jitter_minutes: jitter,
+            post_batch_minutes: batch,
+        }
+    }
+
+    fn at(timestamp: i64) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc.timestamp_opt(timestamp, 0).unwrap()
+    }
+
+    #[test]
+    fn posts_straight_away_without_a_schedule() {
+        assert_eq!(
+            next_post_time(&guild_with_schedule(0, 0, 0), at(1_000)),
+            None
+        );
+    }
+
+    #[test]
+    fn delay_is_added() {
+        let post_at = next_post_time(&guild_with_schedule(10, 0, 0), at(1_000));
+        assert_eq!(post_at, Some(at(1_000 + 600)));
+    }
+
+    #[test]
+    fn jitter_stays_within_its_window() {
+        let guild_model = guild_with_schedule(5, 3, 0);
+        for _ in 0..200 {
+            let post_at = next_post_time(&guild_model, at(1_000)).unwrap();
+            assert!(post_at >= at(1_000 + 300));
+            assert!(post_at <= at(1_000 + 300 + 180));
+        }
+    }
+
+    #[test]
+    fn batches_line_up_with_the_clock() {
+        let guild_model = guild_with_schedule(0, 0, 60);
+        // 10:20 posts in the 11:00 batch.
+        assert_eq!(next_post_time(&guild_model, at(37_200)), Some(at(39_600)));
+        // Something due exactly on a batch boundary goes out in that batch.
+        assert_eq!(next_post_time(&guild_model, at(36_000)), Some(at(36_000)));
+    }
+
+    #[test]
+    fn batching_applies_after_delay_and_jitter() {
+        let guild_model = guild_with_schedule(30, 20, 60);
+        for _ in 0..200 {
+            let post_at = next_post_time(&guild_model, at(36_000)).unwrap();
+            // Due between 10:30 and 10:50, so always the 11:00 batch.
+            assert_eq!(post_at, at(39_600));
+        }
+        let late = next_post_time(&guild_with_schedule(50, 20, 60), at(36_000)).unwrap();
+        assert!(late == at(39_600) || late == at(43_200));
+        assert_eq!(late.timestamp() % 3_600, 0);
+    }
+}
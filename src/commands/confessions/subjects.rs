@@ -0,0 +1,104 @@
+use poise::serenity_prelude as serenity;
+
+use crate::{
+    crypto,
+    entity::confessions,
+    operations::{self, member_settings::SubjectNotifications},
+    Data,
+};
+
+pub const SUBJECT_FIELD: &str = "Subject";
+// Keeps the mentions well inside the message length limit, anyone past this gets a DM instead.
+const MAX_SUBJECT_MENTIONS: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct SubjectRecipients {
+    pub mentions: Vec<u64>,
+    pub direct: Vec<u64>,
+}
+
+impl SubjectRecipients {
+    pub fn mention_content(&self) -> String {
+        self.mentions
+            .iter()
+            .map(|user_id| format!("<@{}>", user_id))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+// The author stays in the public mentions, since anyone can list who has a subject and the one
+// member missing from the pings would give them away. Only their DM, which nobody sees, is skipped.
+pub async fn get_subject_recipients(
+    data: &Data,
+    confession: &confessions::Model,
+    subject: &str,
+) -> anyhow::Result<SubjectRecipients> {
+    let author_id = crypto::decrypt_author(&data.author_key, &confession.author_ref).ok();
+    let users = operations::subjects::get_users_with_subject(
+        &data.database,
+        confession.guild_id,
+        subject.to_owned(),
+    )
+    .await?;
+    let settings = operations::member_settings::get_members_settings(
+        &data.database,
+        confession.guild_id,
+        users.clone(),
+    )
+    .await?;
+    let mut recipients = SubjectRecipients::default();
+    for user_id in users {
+        let notifications = settings
+            .iter()
+            .find(|settings| settings.user_id == user_id)
+            .map(|settings| SubjectNotifications::from(settings.subject_notifications))
+            .unwrap_or(SubjectNotifications::Mention);
+        match notifications {
+            SubjectNotifications::Mention if recipients.mentions.len() < MAX_SUBJECT_MENTIONS => {
+                recipients.mentions.push(user_id)
+            }
+            SubjectNotifications::Mention | SubjectNotifications::Direct
+                if Some(user_id) != author_id =>
+            {
+                recipients.direct.push(user_id)
+            }
+            SubjectNotifications::Mention | SubjectNotifications::Direct => {}
+            SubjectNotifications::Off => {}
+        }
+    }
+    Ok(recipients)
+}
+
+pub async fn notify_subscribers(
+    ctx: &serenity::Context,
+    confession: &confessions::Model,
+    subject: &str,
+    recipients: &SubjectRecipients,
+    link: String,
+) {
+    for user_id in &recipients.direct {
+        let sent = match serenity::UserId(*user_id).create_dm_channel(ctx).await {
+            Ok(dm_channel) => dm_channel
+                .send_message(ctx, |m| {
+                    m.embed(|embed| {
+                        embed
+                            .title(format!("New confession about {}", subject))
+                            .description(format!(
+                                "[Confession #{}]({}) was posted in <#{}>.",
+                                confession.number, link, confession.channel_id
+                            ))
+                            .footer(|f| {
+                                f.text("Change these with /subject_notifications in the server.")
+                            })
+                    })
+                })
+                .await
+                .map(|_| ()),
+            Err(why) => Err(why),
+        };
+        if let Err(why) = sent {
+            println!("Error sending message: {:?}", why);
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use anyhow::anyhow;
+use poise::serenity_prelude as serenity;
+
+use crate::{
+    button,
+    entity::confessions,
+    operations::{self, channels::VettingMode, confessions::ConfessionStatus},
+    Data,
+};
+
+pub const CLAIM_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Default)]
+pub struct Tally {
+    pub approvals: Vec<serenity::UserId>,
+    pub denials: Vec<serenity::UserId>,
+}
+
+impl Tally {
+    pub fn is_approved(&self, quorum: (u32, u32)) -> bool {
+        self.approvals.len() as u32 >= quorum.0 && (self.denials.len() as u32) < quorum.1
+    }
+
+    pub fn is_denied(&self, quorum: (u32, u32)) -> bool {
+        self.denials.len() as u32 >= quorum.1
+    }
+}
+
+pub fn claimed_by_other(confession: &confessions::Model, moderator: serenity::UserId) -> bool {
+    match (confession.claimed_by, confession.claimed_until) {
+        (Some(claimed_by), Some(claimed_until)) => {
+            claimed_by != moderator.0 && claimed_until > chrono::Utc::now()
+        }
+        _ => false,
+    }
+}
+
+pub fn conflict_message(confession: &confessions::Model) -> String {
+    let by = confession
+        .claimed_by
+        .map(|moderator| format!(" by <@{}>", moderator))
+        .unwrap_or_default();
+    match ConfessionStatus::from(confession.status) {
+        ConfessionStatus::Pending => format!("This confession is already being handled{}.", by),
+        status => format!("This confession has already been {}{}.", status, by),
+    }
+}
+
+fn format_voters(voters: &[serenity::UserId]) -> String {
+    voters
+        .iter()
+        .map(|v| format!("<@{}>", v))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Trusted by holding the guild's trusted role, or by enough approved confessions and no denials.
+pub async fn is_trusted(
+    data: &Data,
+    guild_id: u64,
+    author_hash: &str,
+    roles: &[serenity::RoleId],
+) -> anyhow::Result<bool> {
+    let guild = match operations::guild::get_guild(&data.database, guild_id).await? {
+        Some(guild) => guild,
+        None => return Ok(false),
+    };
+    if let Some(trusted_role) = guild.trusted_role {
+        if roles.contains(&serenity::RoleId(trusted_role)) {
+            return Ok(true);
+        }
+    }
+    if guild.trusted_after == 0 {
+        return Ok(false);
+    }
+    let (approved, denied) =
+        operations::confessions::get_author_history(&data.database, guild_id, author_hash).await?;
+    Ok(approved >= guild.trusted_after as u64 && denied == 0)
+}
+
+pub async fn needs_vetting(
+    data: &Data,
+    guild_id: u64,
+    channel_id: u64,
+    author_hash: &str,
+    roles: &[serenity::RoleId],
+) -> anyhow::Result<bool> {
+    let vetting_mode =
+        operations::channels::get_channel_vetting_mode(&data.database, guild_id, channel_id)
+            .await?;
+    match vetting_mode {
+        VettingMode::Always => Ok(true),
+        VettingMode::Never => Ok(false),
+        VettingMode::Untrusted => Ok(!is_trusted(data, guild_id, author_hash, roles).await?),
+    }
+}
+
+pub async fn get_tally(
+    db: &sea_orm::DatabaseConnection,
+    confession_id: i32,
+) -> anyhow::Result<Tally> {
+    let votes = operations::votes::get_votes(db, confession_id).await?;
+    let mut tally = Tally::default();
+    for vote in votes {
+        if vote.approve == 1 {
+            tally.approvals.push(serenity::UserId(vote.moderator_id));
+        } else {
+            tally.denials.push(serenity::UserId(vote.moderator_id));
+        }
+    }
+    Ok(tally)
+}
+
+pub fn add_vetting_buttons(
+    components: &mut serenity::CreateComponents,
+    confession_id: i32,
+) -> &mut serenity::CreateComponents {
+    components
+        .create_action_row(|action_row| add_review_buttons(action_row, confession_id))
+        .create_action_row(|action_row| {
+            action_row.add_button(
+                serenity::CreateButton::default()
+                    .label("Content warning")
+                    .style(serenity::ButtonStyle::Secondary)
+                    .custom_id(button::ConfessionButton::ContentWarning(confession_id).to_string())
+                    .to_owned(),
+            )
+        })
+}
+
+fn add_review_buttons(
+    action_row: &mut serenity::CreateActionRow,
+    confession_id: i32,
+) -> &mut serenity::CreateActionRow {
+    action_row
+        .add_button(
+            serenity::CreateButton::default()
+                .label("Approve")
+                .style(serenity::ButtonStyle::Success)
+                .custom_id(button::ConfessionButton::ApproveConfession(confession_id).to_string())
+                .to_owned(),
+        )
+        .add_button(
+            serenity::CreateButton::default()
+                .label("Deny")
+                .style(serenity::ButtonStyle::Danger)
+                .custom_id(button::ConfessionButton::DenyConfession(confession_id).to_string())
+                .to_owned(),
+        )
+        .add_button(
+            serenity::CreateButton::default()
+                .label("Edit & Approve")
+                .style(serenity::ButtonStyle::Primary)
+                .custom_id(button::ConfessionButton::EditConfession(confession_id).to_string())
+                .to_owned(),
+        )
+        .add_button(
+            serenity::CreateButton::default()
+                .label("Claim")
+                .style(serenity::ButtonStyle::Secondary)
+                .custom_id(button::ConfessionButton::ClaimConfession(confession_id).to_string())
+                .to_owned(),
+        )
+        .add_button(
+            serenity::CreateButton::default()
+                .label("Block author")
+                .style(serenity::ButtonStyle::Secondary)
+                .custom_id(button::ConfessionButton::BlockAuthor(confession_id).to_string())
+                .to_owned(),
+        )
+}
+
+pub fn vetting_content(
+    confession: &confessions::Model,
+    tally: &Tally,
+    quorum: (u32, u32),
+) -> String {
+    let mut content = format!("Confession going to <#{}>", confession.channel_id);
+    if let Some(edited_by) = confession.edited_by {
+        content.push_str(&format!("\nEdited by <@{}>", edited_by));
+    }
+    if let (Some(claimed_by), Some(claimed_until)) =
+        (confession.claimed_by, confession.claimed_until)
+    {
+        if claimed_until > chrono::Utc::now()
+            && ConfessionStatus::from(confession.status) == ConfessionStatus::Pending
+        {
+            content.push_str(&format!(
+                "\nClaimed by <@{}> until <t:{}:t>",
+                claimed_by,
+                claimed_until.timestamp()
+            ));
+        }
+    }
+    if quorum != (1, 1) || !tally.approvals.is_empty() || !tally.denials.is_empty() {
+        content.push_str(&format!(
+            "\nApprovals: {}/{} {}\nDenials: {}/{} {}",
+            tally.approvals.len(),
+            quorum.0,
+            format_voters(&tally.approvals),
+            tally.denials.len(),
+            quorum.1,
+            format_voters(&tally.denials),
+        ));
+    }
+    content
+}
+
+pub async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    content: String,
+) {
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data.content(content).ephemeral(true)
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+async fn update_tally(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    content: String,
+) {
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|response_data| {
+                    response_data
+                        .content(content)
+                        .allowed_mentions(|mentions| mentions.empty_parse())
+                })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+    }
+}
+
+async fn cast_vote(
+    data: &Data,
+    moderator: serenity::UserId,
+    confession_id: i32,
+    approve: bool,
+) -> anyhow::Result<(confessions::Model, Tally, (u32, u32))> {
+    let confession =
+        match operations::confessions::get_confession(&data.database, confession_id).await? {
+            Some(confession) => confession,
+            None => return Err(anyhow!("Confession not found")),
+        };
+    let status = ConfessionStatus::from(confession.status);
+    if status != ConfessionStatus::Pending {
+        return Err(anyhow!("This confession has already been {}.", status));
+    }
+    if claimed_by_other(&confession, moderator) {
+        return Err(anyhow!(conflict_message(&confession)));
+    }
+    operations::votes::set_vote(&data.database, confession_id, moderator.0, approve).await?;
+    let tally = get_tally(&data.database, confession_id).await?;
+    let quorum =
+        operations::guild::get_guild_vetting_quorum(&data.database, confession.guild_id).await?;
+    Ok((confession, tally, quorum))
+}
+
+// Atomically moves the confession out of pending, failing if another moderator got there first.
+pub async fn take_confession(
+    data: &Data,
+    moderator: serenity::UserId,
+    confession_id: i32,
+    status: ConfessionStatus,
+) -> anyhow::Result<confessions::Model> {
+    let taken = operations::confessions::resolve_confession(
+        &data.database,
+        confession_id,
+        moderator.0,
+        status,
+    )
+    .await?;
+    let confession =
+        match operations::confessions::get_confession(&data.database, confession_id).await? {
+            Some(confession) => confession,
+            None => return Err(anyhow!("Confession not found")),
+        };
+    if !taken {
+        return Err(anyhow!(conflict_message(&confession)));
+    }
+    Ok(confession)
+}
+
+pub async fn claim_confession(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let until = chrono::Utc::now() + chrono::Duration::minutes(CLAIM_MINUTES);
+    let claimed = operations::confessions::claim_confession(
+        &data.database,
+        confession_id,
+        component.user.id.0,
+        until,
+    )
+    .await;
+    let confession = operations::confessions::get_confession(&data.database, confession_id).await;
+    let confession = match (claimed, confession) {
+        (Ok(true), Ok(Some(confession))) => confession,
+        (Ok(false), Ok(Some(confession))) => {
+            respond_ephemeral(ctx, component, conflict_message(&confession)).await;
+            return;
+        }
+        (_, Ok(None)) => {
+            respond_ephemeral(ctx, component, "Confession not found".to_owned()).await;
+            return;
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            respond_ephemeral(ctx, component, format!("Error claiming confession: {}", e)).await;
+            return;
+        }
+    };
+    let tally = get_tally(&data.database, confession_id)
+        .await
+        .unwrap_or_default();
+    let quorum = operations::guild::get_guild_vetting_quorum(&data.database, confession.guild_id)
+        .await
+        .unwrap_or((1, 1));
+    update_tally(ctx, component, vetting_content(&confession, &tally, quorum)).await;
+}
+
+pub enum Approval {
+    Waiting(String),
+    // Carries the time the confession will be posted at when the guild delays posts.
+    Accepted(Tally, Option<chrono::DateTime<chrono::Utc>>),
+}
+
+// Casts an approval and posts or schedules the confession once the quorum is met.
+pub async fn approve(
+    ctx: &serenity::Context,
+    data: &Data,
+    moderator: serenity::UserId,
+    confession_id: i32,
+) -> anyhow::Result<Approval> {
+    let (confession, tally, quorum) = cast_vote(data, moderator, confession_id, true).await?;
+    if !tally.is_approved(quorum) {
+        return Ok(Approval::Waiting(vetting_content(
+            &confession,
+            &tally,
+            quorum,
+        )));
+    }
+    let mut confession =
+        take_confession(data, moderator, confession.id, ConfessionStatus::Approved).await?;
+    match super::schedule::post_or_schedule(ctx, data, confession.clone()).await {
+        Ok(post_at) => Ok(Approval::Accepted(tally, post_at)),
+        Err(why) => {
+            println!("Error posting confession: {:?}", why);
+            // Put it back up for review so it isn't stuck as approved without being posted.
+            confession.status = ConfessionStatus::Pending.into();
+            if let Err(why) =
+                operations::confessions::set_confession(&data.database, confession).await
+            {
+                println!("Error saving confession: {:?}", why);
+            }
+            Err(anyhow!("Error posting confession: {}", why.to_string()))
+        }
+    }
+}
+
+pub fn accepted_content(tally: &Tally, post_at: Option<chrono::DateTime<chrono::Utc>>) -> String {
+    let mut content = format!("Confession accepted by {}", format_voters(&tally.approvals));
+    if let Some(post_at) = post_at {
+        content.push_str(&format!(", posting <t:{}:R>", post_at.timestamp()));
+    }
+    content
+}
+
+pub async fn approve_confession(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) -> bool {
+    let (tally, post_at) = match approve(ctx, data, component.user.id, confession_id).await {
+        Ok(Approval::Accepted(tally, post_at)) => (tally, post_at),
+        Ok(Approval::Waiting(content)) => {
+            update_tally(ctx, component, content).await;
+            return false;
+        }
+        Err(e) => {
+            respond_ephemeral(ctx, component, e.to_string()).await;
+            return false;
+        }
+    };
+    if let Err(why) = component
+        .create_interaction_response(&ctx.http, |response| {
+            response.interaction_response_data(|response_data| {
+                response_data
+                    .content(accepted_content(&tally, post_at))
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+        })
+        .await
+    {
+        println!("Error sending message: {:?}", why);
+        return false;
+    }
+    true
+}
+
+pub async fn deny_confession(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let (confession, tally, quorum) =
+        match cast_vote(data, component.user.id, confession_id, false).await {
+            Ok(vote) => vote,
+            Err(e) => {
+                respond_ephemeral(ctx, component, e.to_string()).await;
+                return;
+            }
+        };
+    if tally.is_denied(quorum) {
+        super::deny::open_deny(ctx, component, data, confession_id).await;
+    } else {
+        update_tally(ctx, component, vetting_content(&confession, &tally, quorum)).await;
+    }
+}
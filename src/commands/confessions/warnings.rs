@@ -0,0 +1,129 @@
+use anyhow::anyhow;
+use poise::{serenity_prelude as serenity, Modal};
+
+use super::vetting;
+use crate::{
+    operations::{self, confessions::ConfessionStatus},
+    util, Data,
+};
+
+pub const CONTENT_WARNING_FIELD: &str = "Content warning";
+
+#[derive(Debug, Modal)]
+#[name = "Content warning"]
+struct ContentWarningModal {
+    #[name = "Content warning (leave empty to remove)"]
+    #[max_length = 100]
+    content_warning: Option<String>,
+}
+
+pub fn clean_content_warning(content_warning: Option<String>) -> Option<String> {
+    content_warning
+        .map(|cw| cw.trim().to_owned())
+        .filter(|cw| !cw.is_empty())
+}
+
+// Shows the warning up front and keeps the confession itself behind spoiler markup.
+pub fn hide_behind_warning(content_warning: &str, content: &str) -> String {
+    match content.is_empty() {
+        true => format!("**CW: {}**", content_warning),
+        false => format!(
+            "**CW: {}**\n||{}||",
+            content_warning,
+            content.replace("||", "\\|\\|")
+        ),
+    }
+}
+
+pub async fn open_content_warning_modal(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    data: &Data,
+    confession_id: i32,
+) {
+    let confession =
+        match operations::confessions::get_confession(&data.database, confession_id).await {
+            Ok(Some(confession)) => confession,
+            Ok(None) => {
+                vetting::respond_ephemeral(ctx, component, "Confession not found".to_owned()).await;
+                return;
+            }
+            Err(why) => {
+                println!("Error getting confession: {:?}", why);
+                return;
+            }
+        };
+    if ConfessionStatus::from(confession.status) != ConfessionStatus::Pending
+        || vetting::claimed_by_other(&confession, component.user.id)
+    {
+        vetting::respond_ephemeral(ctx, component, vetting::conflict_message(&confession)).await;
+        return;
+    }
+    let modal = util::execute_component_modal(
+        ctx,
+        component,
+        Some(ContentWarningModal {
+            content_warning: confession.content_warning.clone(),
+        }),
+        None,
+    )
+    .await;
+    let (modal_interaction, cw_modal) = match modal {
+        Ok(Some(submitted)) => submitted,
+        Ok(None) => return,
+        Err(why) => {
+            println!("Error getting modal: {:?}", why);
+            return;
+        }
+    };
+    let content_warning = clean_content_warning(cw_modal.content_warning);
+    let set_result = match operations::confessions::set_content_warning(
+        &data.database,
+        confession_id,
+        component.user.id.0,
+        content_warning.clone(),
+    )
+    .await
+    {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(anyhow!(vetting::conflict_message(&confession))),
+        Err(e) => Err(e),
+    };
+    let response = match set_result {
+        Ok(_) => {
+            modal_interaction
+                .create_interaction_response(&ctx.http, |response_builder| {
+                    response_builder
+                        .kind(serenity::InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|response_data| {
+                            if let Some(mut embed) = component.message.embeds.first().cloned() {
+                                embed
+                                    .fields
+                                    .retain(|field| field.name != CONTENT_WARNING_FIELD);
+                                let mut warned_embed = serenity::CreateEmbed::from(embed);
+                                if let Some(cw) = &content_warning {
+                                    warned_embed.field(CONTENT_WARNING_FIELD, cw, false);
+                                }
+                                response_data.set_embed(warned_embed);
+                            }
+                            response_data
+                        })
+                })
+                .await
+        }
+        Err(e) => {
+            modal_interaction
+                .create_interaction_response(&ctx.http, |response_builder| {
+                    response_builder.interaction_response_data(|response_data| {
+                        response_data
+                            .content(format!("Error setting content warning: {}", e))
+                            .ephemeral(true)
+                    })
+                })
+                .await
+        }
+    };
+    if let Err(why) = response {
+        println!("Error sending message: {:?}", why);
+    }
+}
@@ -3,7 +3,10 @@ use poise::serenity_prelude as serenity;
 use tracing::info;
 
 // this is a blank struct initialised in main.rs and then imported here
-use crate::{auth, operations, Data};
+use crate::{
+    auth, commands::confessions::schedule, media, operations, operations::guild::DuplicateAction,
+    Data,
+};
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
@@ -80,3 +83,319 @@ pub async fn set_mod_role(
     };
     Ok(())
 }
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_reply_vetting(
+    ctx: Context<'_>,
+    #[description = "Should replies be vetted"] vetted: bool,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let found_guild = operations::guild::get_guild(&db, this_guild).await;
+    let response = match found_guild {
+        Ok(Some(mut guild_model)) => {
+            guild_model.vet_replies = if vetted { 1 } else { 0 };
+            match operations::guild::set_guild(&db, guild_model).await {
+                Ok(_) => format!("Reply vetting: {}", vetted),
+                Err(e) => e.to_string(),
+            }
+        }
+        _ => "Guild not found. Have you used initialise?".to_owned(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        info!("Error sending message: {:?}", why_discord_say);
+    };
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_vetting_quorum(
+    ctx: Context<'_>,
+    #[description = "Approvals needed to post a confession"]
+    #[min = 1]
+    approvals: u32,
+    #[description = "Denials needed to deny a confession"]
+    #[min = 1]
+    denials: u32,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let found_guild = operations::guild::get_guild(&db, this_guild).await;
+    let response = match found_guild {
+        Ok(Some(mut guild_model)) => {
+            guild_model.required_approvals = approvals.max(1);
+            guild_model.required_denials = denials.max(1);
+            match operations::guild::set_guild(&db, guild_model).await {
+                Ok(_) => format!(
+                    "Confessions now need {} approval(s) to post and {} denial(s) to deny.",
+                    approvals.max(1),
+                    denials.max(1)
+                ),
+                Err(e) => e.to_string(),
+            }
+        }
+        _ => "Guild not found. Have you used initialise?".to_owned(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        info!("Error sending message: {:?}", why_discord_say);
+    };
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_pending_expiry(
+    ctx: Context<'_>,
+    #[description = "Hours before a pending confession is denied (0 to never expire)"] hours: u32,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let found_guild = operations::guild::get_guild(&db, this_guild).await;
+    let response = match found_guild {
+        Ok(Some(mut guild_model)) => {
+            guild_model.pending_expiry_hours = hours;
+            match operations::guild::set_guild(&db, guild_model).await {
+                Ok(_) => match hours {
+                    0 => "Pending confessions no longer expire.".to_owned(),
+                    _ => format!("Pending confessions are denied after {} hour(s).", hours),
+                },
+                Err(e) => e.to_string(),
+            }
+        }
+        _ => "Guild not found. Have you used initialise?".to_owned(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        info!("Error sending message: {:?}", why_discord_say);
+    };
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_trusted(
+    ctx: Context<'_>,
+    #[description = "Members with this role are trusted"] role: Option<serenity::RoleId>,
+    #[description = "Approved confessions with no denials needed to be trusted (0 to disable)"]
+    approved_confessions: u32,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let found_guild = operations::guild::get_guild(&db, this_guild).await;
+    let response = match found_guild {
+        Ok(Some(mut guild_model)) => {
+            guild_model.trusted_role = role.map(|r| r.0);
+            guild_model.trusted_after = approved_confessions;
+            match operations::guild::set_guild(&db, guild_model).await {
+                Ok(_) => format!(
+                    "Trusted role: {}\nTrusted after: {}",
+                    role.map(|r| format!("<@&{}>", r))
+                        .unwrap_or("none".to_owned()),
+                    match approved_confessions {
+                        0 => "never".to_owned(),
+                        n => format!("{} approved confession(s)", n),
+                    }
+                ),
+                Err(e) => e.to_string(),
+            }
+        }
+        _ => "Guild not found. Have you used initialise?".to_owned(),
+    };
+    if let Err(why_discord_say) = ctx
+        .send(|builder| {
+            builder
+                .content(response)
+                .allowed_mentions(|mentions| mentions.empty_parse())
+        })
+        .await
+    {
+        info!("Error sending message: {:?}", why_discord_say);
+    };
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_duplicate_action(
+    ctx: Context<'_>,
+    #[description = "What to do with near-duplicate confessions"] action: DuplicateAction,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let found_guild = operations::guild::get_guild(&db, this_guild).await;
+    let response = match found_guild {
+        Ok(Some(mut guild_model)) => {
+            guild_model.duplicate_action = action.into();
+            match operations::guild::set_guild(&db, guild_model).await {
+                Ok(_) => format!("Near-duplicate confessions: {}", action),
+                Err(e) => e.to_string(),
+            }
+        }
+        _ => "Guild not found. Have you used initialise?".to_owned(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        info!("Error sending message: {:?}", why_discord_say);
+    };
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_attachment_rules(
+    ctx: Context<'_>,
+    #[description = "Files allowed per confession"] max_files: Option<u32>,
+    #[description = "Largest file allowed, in MB"] max_size_mb: Option<u32>,
+    #[description = "Allow video files"] allow_video: Option<bool>,
+    #[description = "Allow audio files"] allow_audio: Option<bool>,
+    #[description = "Other MIME types to allow, comma separated (e.g. application/pdf, text/*), or none"]
+    other_types: Option<String>,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let found_guild = operations::guild::get_guild(&db, this_guild).await;
+    let response = match found_guild {
+        Ok(Some(_)) if max_files.is_some_and(|n| !(1..=media::MAX_ATTACHMENTS).contains(&n)) => {
+            format!(
+                "Confessions can have between 1 and {} files.",
+                media::MAX_ATTACHMENTS
+            )
+        }
+        Ok(Some(_))
+            if max_size_mb.is_some_and(|mb| !(1..=media::MAX_ATTACHMENT_MB).contains(&mb)) =>
+        {
+            format!(
+                "Files can be between 1 and {} MB.",
+                media::MAX_ATTACHMENT_MB
+            )
+        }
+        Ok(Some(mut guild_model)) => {
+            if let Some(max_files) = max_files {
+                guild_model.max_attachments = max_files;
+            }
+            if let Some(max_size_mb) = max_size_mb {
+                guild_model.max_attachment_mb = max_size_mb;
+            }
+            if let Some(allow_video) = allow_video {
+                guild_model.allow_video = allow_video as i8;
+            }
+            if let Some(allow_audio) = allow_audio {
+                guild_model.allow_audio = allow_audio as i8;
+            }
+            if let Some(other_types) = other_types {
+                let types = media::parse_types(&other_types);
+                guild_model.allowed_file_types = match types.is_empty() || types == ["none"] {
+                    true => None,
+                    false => Some(types.join(",")),
+                };
+            }
+            let rules = media::AttachmentRules::from_guild(Some(&guild_model));
+            match operations::guild::set_guild(&db, guild_model).await {
+                Ok(_) => rules.describe(),
+                Err(e) => e.to_string(),
+            }
+        }
+        _ => "Guild not found. Have you used initialise?".to_owned(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        info!("Error sending message: {:?}", why_discord_say);
+    };
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn set_posting_schedule(
+    ctx: Context<'_>,
+    #[description = "Minutes to wait before posting an approved confession"] delay_minutes: u32,
+    #[description = "Up to this many extra minutes, picked at random"] jitter_minutes: u32,
+    #[description = "Only post at multiples of this many minutes, e.g. 60 for on the hour (0 to disable)"]
+    batch_minutes: u32,
+) -> Result<(), Error> {
+    let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
+    match auth_res {
+        Ok(authorised) => {
+            if !authorised {
+                return Ok(());
+            }
+        }
+        Err(_) => return Ok(()),
+    };
+    let db = ctx.data().database.clone();
+    let this_guild = ctx.guild_id().unwrap().0;
+    let found_guild = operations::guild::get_guild(&db, this_guild).await;
+    let response = match found_guild {
+        Ok(Some(_))
+            if [delay_minutes, jitter_minutes, batch_minutes]
+                .iter()
+                .any(|minutes| *minutes > schedule::MAX_SCHEDULE_MINUTES) =>
+        {
+            format!(
+                "Each setting can be at most {} minutes.",
+                schedule::MAX_SCHEDULE_MINUTES
+            )
+        }
+        Ok(Some(mut guild_model)) => {
+            guild_model.post_delay_minutes = delay_minutes;
+            guild_model.post_jitter_minutes = jitter_minutes;
+            guild_model.post_batch_minutes = batch_minutes;
+            let description = schedule::describe_schedule(&guild_model);
+            match operations::guild::set_guild(&db, guild_model).await {
+                Ok(_) => description,
+                Err(e) => e.to_string(),
+            }
+        }
+        _ => "Guild not found. Have you used initialise?".to_owned(),
+    };
+    if let Err(why_discord_say) = ctx.say(response).await {
+        info!("Error sending message: {:?}", why_discord_say);
+    };
+    Ok(())
+}
@@ -8,6 +8,7 @@ type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
 type FrameworkContext<'a> = poise::FrameworkContext<'a, Data, Error>;
 
+pub mod autocomplete;
 pub mod channel;
 pub mod confessions;
 pub mod guild;
@@ -3,7 +3,11 @@ use poise::serenity_prelude as serenity;
 use tracing::info;
 
 // this is a blank struct initialised in main.rs and then imported here
-use crate::{auth, operations, Data};
+use crate::{
+    auth,
+    operations::{self, member_settings::SubjectNotifications},
+    Data,
+};
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
@@ -30,6 +34,7 @@ pub async fn add_subject(
     {
         ctx.say(format!("Error adding subject: {}", why)).await?;
     } else {
+        ctx.data().autocomplete.subjects.invalidate(&this_guild);
         ctx.say(format!("Added subject: {}", subject)).await?;
     }
     Ok(())
@@ -52,7 +57,9 @@ pub async fn get_subjects(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command(slash_command, prefix_command, guild_only = true)]
 pub async fn remove_subject(
     ctx: Context<'_>,
-    #[description = "Subject"] subject: String,
+    #[description = "Subject"]
+    #[autocomplete = "crate::commands::autocomplete::subject"]
+    subject: String,
 ) -> Result<(), Error> {
     let auth_res = auth::respond_based_on_auth_context(&ctx, auth::Auth::Admin).await;
     if let Err(_) = auth_res {
@@ -70,6 +77,7 @@ pub async fn remove_subject(
     {
         ctx.say(format!("Error removing subject: {}", why)).await?;
     } else {
+        ctx.data().autocomplete.subjects.invalidate(&this_guild);
         ctx.say(format!("Removed subject: {}", subject)).await?;
     }
     Ok(())
@@ -78,7 +86,9 @@ pub async fn remove_subject(
 #[poise::command(slash_command, prefix_command, guild_only = true)]
 pub async fn add_user_subjects(
     ctx: Context<'_>,
-    #[description = "Subject delimited by space"] subjects: String,
+    #[description = "Subject delimited by space"]
+    #[autocomplete = "crate::commands::autocomplete::subject_list"]
+    subjects: String,
     #[description = "User"] user: Option<serenity::Member>,
 ) -> Result<(), Error> {
     let db = ctx.data().database.clone();
@@ -108,6 +118,10 @@ pub async fn add_user_subjects(
     {
         ctx.say(format!("Error adding subjects: {}", why)).await?;
     } else {
+        ctx.data()
+            .autocomplete
+            .user_subjects
+            .invalidate(&(this_guild, user_id));
         let fmted = send_subjects
             .into_iter()
             .map(|x| format!("- {}", x))
@@ -141,7 +155,9 @@ pub async fn get_user_subjects(
 #[poise::command(slash_command, prefix_command, guild_only = true)]
 pub async fn remove_user_subjects(
     ctx: Context<'_>,
-    #[description = "Subject delimited by space"] subjects: String,
+    #[description = "Subject delimited by space"]
+    #[autocomplete = "crate::commands::autocomplete::own_subject_list"]
+    subjects: String,
     #[description = "User"] user: Option<serenity::Member>,
 ) -> Result<(), Error> {
     let db = ctx.data().database.clone();
@@ -171,6 +187,10 @@ pub async fn remove_user_subjects(
     {
         ctx.say(format!("Error removing subjects: {}", why)).await?;
     } else {
+        ctx.data()
+            .autocomplete
+            .user_subjects
+            .invalidate(&(this_guild, user_id));
         let fmted = send_subjects
             .into_iter()
             .map(|x| format!("- {}", x))
@@ -184,7 +204,9 @@ pub async fn remove_user_subjects(
 #[poise::command(slash_command, prefix_command, guild_only = true)]
 pub async fn get_users_with_subject(
     ctx: Context<'_>,
-    #[description = "Subject"] subject: String,
+    #[description = "Subject"]
+    #[autocomplete = "crate::commands::autocomplete::subject"]
+    subject: String,
 ) -> Result<(), Error> {
     let db = ctx.data().database.clone();
     let this_guild = ctx.guild_id().unwrap().0;
@@ -203,3 +225,25 @@ pub async fn get_users_with_subject(
     .await?;
     Ok(())
 }
+
+#[poise::command(slash_command, prefix_command, guild_only = true)]
+pub async fn subject_notifications(
+    ctx: Context<'_>,
+    #[description = "How you hear about confessions on your subjects"]
+    notifications: SubjectNotifications,
+) -> Result<(), Error> {
+    let set_result = operations::member_settings::set_subject_notifications(
+        &ctx.data().database,
+        ctx.guild_id().unwrap().0,
+        ctx.author().id.0,
+        notifications,
+    )
+    .await;
+    let response = match set_result {
+        Ok(_) => format!("Subject notifications set to {}.", notifications),
+        Err(e) => e.to_string(),
+    };
+    ctx.send(|builder| builder.content(response).ephemeral(true))
+        .await?;
+    Ok(())
+}
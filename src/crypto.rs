@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+const NONCE_LENGTH: usize = 12;
+
+pub type AuthorKey = [u8; 32];
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn derive_key(secret: &str) -> AuthorKey {
+    Sha256::digest(secret.as_bytes()).into()
+}
+
+// Author references are `hex(nonce || ciphertext)` so the same author never encrypts the same way twice.
+pub fn encrypt_author(key: &AuthorKey, user_id: u64) -> Result<String> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    match cipher.encrypt(&nonce, user_id.to_le_bytes().as_ref()) {
+        Ok(ciphertext) => {
+            let mut sealed = nonce.to_vec();
+            sealed.extend(ciphertext);
+            Ok(hex::encode(sealed))
+        }
+        Err(e) => Err(anyhow!("Error encrypting author: {:?}", e)),
+    }
+}
+
+pub fn decrypt_author(key: &AuthorKey, author_ref: &str) -> Result<u64> {
+    let sealed = match hex::decode(author_ref) {
+        Ok(sealed) => sealed,
+        Err(e) => return Err(anyhow!("Error decoding author reference: {:?}", e)),
+    };
+    if sealed.len() <= NONCE_LENGTH {
+        return Err(anyhow!("Author reference is too short"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    match cipher.decrypt(Nonce::from_slice(nonce), ciphertext) {
+        Ok(plaintext) => match <[u8; 8]>::try_from(plaintext.as_slice()) {
+            Ok(bytes) => Ok(u64::from_le_bytes(bytes)),
+            Err(_) => Err(anyhow!("Author reference has the wrong length")),
+        },
+        Err(e) => Err(anyhow!("Error decrypting author: {:?}", e)),
+    }
+}
+
+// Keyed per guild, so the same member can't be linked across guilds by comparing hashes.
+pub fn hash_author(key: &AuthorKey, guild_id: u64, user_id: u64) -> String {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(&guild_id.to_le_bytes());
+    mac.update(&user_id.to_le_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// Scoped to a single confession so reply pseudonyms can't be linked across threads.
+pub fn hash_replier(key: &AuthorKey, confession_id: i32, user_id: u64) -> String {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(b"reply");
+    mac.update(&confession_id.to_le_bytes());
+    mac.update(&user_id.to_le_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// Scoped to a single poll so votes can't be linked to each other or to confessions.
+pub fn hash_poll_voter(key: &AuthorKey, poll_id: i32, user_id: u64) -> String {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(b"poll");
+    mac.update(&poll_id.to_le_bytes());
+    mac.update(&user_id.to_le_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
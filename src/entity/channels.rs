@@ -10,6 +10,9 @@ pub struct Model {
     pub id: u64,
     pub guild_id: u64,
     pub channel_use: i32,
+    pub vetting_mode: i32,
+    pub require_content_warning: i8,
+    pub host_id: Option<u64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
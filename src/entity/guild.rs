@@ -9,6 +9,22 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: u64,
     pub admin_role: Option<u64>,
+    pub vet_replies: i8,
+    pub required_approvals: u32,
+    pub required_denials: u32,
+    pub pending_expiry_hours: u32,
+    pub trusted_role: Option<u64>,
+    pub trusted_after: u32,
+    pub duplicate_action: i32,
+    pub max_attachments: u32,
+    pub max_attachment_mb: u32,
+    pub allow_video: i8,
+    pub allow_audio: i8,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub allowed_file_types: Option<String>,
+    pub post_delay_minutes: u32,
+    pub post_jitter_minutes: u32,
+    pub post_batch_minutes: u32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
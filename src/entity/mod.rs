@@ -3,8 +3,25 @@
 pub mod prelude;
 
 pub mod channels;
+pub mod confession_attachments;
+pub mod confession_polls;
+pub mod confession_replies;
+pub mod confession_votes;
+pub mod confessions;
+pub mod direct_confessions;
+pub mod direct_messages;
 pub mod guild;
+pub mod guild_blocks;
 pub mod guild_confessions;
+pub mod guild_deny_reasons;
+pub mod guild_filter_rules;
 pub mod guild_members;
+pub mod guild_pseudonyms;
+pub mod guild_rate_limits;
 pub mod guild_subjects;
 pub mod guild_user_subjects;
+pub mod member_settings;
+pub mod modmail_threads;
+pub mod poll_votes;
+pub mod reply_pseudonyms;
+pub mod scheduled_posts;
@@ -1,8 +1,28 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.2
 
+// Entities are used through their own modules, so most of these go unused.
+#![allow(unused_imports)]
+
 pub use super::channels::Entity as Channels;
+pub use super::confession_attachments::Entity as ConfessionAttachments;
+pub use super::confession_polls::Entity as ConfessionPolls;
+pub use super::confession_replies::Entity as ConfessionReplies;
+pub use super::confession_votes::Entity as ConfessionVotes;
+pub use super::confessions::Entity as Confessions;
+pub use super::direct_confessions::Entity as DirectConfessions;
+pub use super::direct_messages::Entity as DirectMessages;
 pub use super::guild::Entity as Guild;
+pub use super::guild_blocks::Entity as GuildBlocks;
 pub use super::guild_confessions::Entity as GuildConfessions;
+pub use super::guild_deny_reasons::Entity as GuildDenyReasons;
+pub use super::guild_filter_rules::Entity as GuildFilterRules;
 pub use super::guild_members::Entity as GuildMembers;
+pub use super::guild_pseudonyms::Entity as GuildPseudonyms;
+pub use super::guild_rate_limits::Entity as GuildRateLimits;
 pub use super::guild_subjects::Entity as GuildSubjects;
 pub use super::guild_user_subjects::Entity as GuildUserSubjects;
+pub use super::member_settings::Entity as MemberSettings;
+pub use super::modmail_threads::Entity as ModmailThreads;
+pub use super::poll_votes::Entity as PollVotes;
+pub use super::reply_pseudonyms::Entity as ReplyPseudonyms;
+pub use super::scheduled_posts::Entity as ScheduledPosts;
@@ -0,0 +1,271 @@
+use regex::Regex;
+
+use crate::{
+    entity::guild_filter_rules,
+    operations::filters::{FilterAction, FilterKind},
+};
+
+const INVITE_PATTERN: &str = r"(?i)\b(discord\.gg|discord(app)?\.com/invite)/[\w-]+";
+const URL_PATTERN: &str = r"(?i)\bhttps?://\S+";
+const MASS_MENTION_PATTERN: &str = r"@(everyone|here)|<@&\d+>";
+// Three or more combining marks stacked on one character.
+const ZALGO_PATTERN: &str =
+    r"[\u{0300}-\u{036F}\u{1AB0}-\u{1AFF}\u{1DC0}-\u{1DFF}\u{20D0}-\u{20FF}\u{FE20}-\u{FE2F}]{3,}";
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Default)]
+pub struct FilterOutcome {
+    pub content: String,
+    pub flags: Vec<String>,
+    pub rejection: Option<String>,
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+// `\b` only works next to a word character, so words like `c++` or `:emoji:` leave it off that side.
+fn word_pattern(word: &str) -> String {
+    let start = match is_word_char(word.chars().next()) {
+        true => r"\b",
+        false => "",
+    };
+    let end = match is_word_char(word.chars().last()) {
+        true => r"\b",
+        false => "",
+    };
+    format!("(?i){}{}{}", start, regex::escape(word), end)
+}
+
+// `None` for rules that aren't matched with a regex, or are missing their pattern.
+pub fn rule_regex(kind: FilterKind, pattern: Option<&str>) -> Result<Option<Regex>, regex::Error> {
+    let source = match (kind, pattern) {
+        // A blank word or pattern would match every confession.
+        (FilterKind::Word | FilterKind::Regex, Some(pattern)) if pattern.trim().is_empty() => {
+            return Ok(None)
+        }
+        (FilterKind::Word, Some(word)) => word_pattern(word.trim()),
+        (FilterKind::Regex, Some(pattern)) => pattern.to_owned(),
+        (FilterKind::Invite, _) => INVITE_PATTERN.to_owned(),
+        (FilterKind::Url, _) => URL_PATTERN.to_owned(),
+        (FilterKind::MassMention, _) => MASS_MENTION_PATTERN.to_owned(),
+        (FilterKind::Zalgo, _) => ZALGO_PATTERN.to_owned(),
+        _ => return Ok(None),
+    };
+    Regex::new(&source).map(Some)
+}
+
+pub fn max_lines(pattern: Option<&str>) -> Option<usize> {
+    pattern.and_then(|pattern| pattern.trim().parse::<usize>().ok())
+}
+
+pub fn describe_rule(rule: &guild_filter_rules::Model) -> String {
+    let pattern = rule.pattern.clone().unwrap_or_default();
+    match FilterKind::from(rule.kind) {
+        FilterKind::Word => format!("the banned word `{}`", pattern),
+        FilterKind::Regex => format!("a blocked pattern (filter {})", rule.id),
+        FilterKind::Invite => "an invite link".to_owned(),
+        FilterKind::Url => "a link".to_owned(),
+        FilterKind::MassMention => "a mass mention".to_owned(),
+        FilterKind::MaxLines => format!("more than {} lines", pattern),
+        FilterKind::Zalgo => "zalgo text".to_owned(),
+    }
+}
+
+// Rules run in order, so a redaction earlier on can stop a later rule from matching.
+pub fn apply_filters(rules: &[guild_filter_rules::Model], content: &str) -> FilterOutcome {
+    let mut outcome = FilterOutcome {
+        content: content.to_owned(),
+        ..Default::default()
+    };
+    for rule in rules {
+        let kind = FilterKind::from(rule.kind);
+        let action = FilterAction::from(rule.action);
+        let pattern = rule.pattern.as_deref();
+        let matched = match kind {
+            FilterKind::MaxLines => match max_lines(pattern) {
+                Some(max) if outcome.content.lines().count() > max => {
+                    if action == FilterAction::Redact {
+                        outcome.content = outcome
+                            .content
+                            .lines()
+                            .take(max)
+                            .collect::<Vec<&str>>()
+                            .join("\n");
+                    }
+                    true
+                }
+                _ => false,
+            },
+            _ => match rule_regex(kind, pattern) {
+                Ok(Some(regex)) if regex.is_match(&outcome.content) => {
+                    if action == FilterAction::Redact {
+                        outcome.content =
+                            regex.replace_all(&outcome.content, REDACTED).into_owned();
+                    }
+                    true
+                }
+                _ => false,
+            },
+        };
+        if !matched {
+            continue;
+        }
+        let description = describe_rule(rule);
+        match action {
+            FilterAction::Reject => {
+                outcome.rejection = Some(format!("it contains {}", description));
+                return outcome;
+            }
+            FilterAction::Flag => outcome.flags.push(format!("Contains {}", description)),
+            FilterAction::Redact => outcome.flags.push(format!("Redacted {}", description)),
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(
+        id: i32,
+        kind: FilterKind,
+        pattern: Option<&str>,
+        action: FilterAction,
+    ) -> guild_filter_rules::Model {
+        guild_filter_rules::Model {
+            id,
+            guild_id: 1,
+            kind: kind.into(),
+            pattern: pattern.map(|pattern| pattern.to_owned()),
+            action: action.into(),
+        }
+    }
+
+    #[test]
+    fn clean_content_passes() {
+        let rules = [
+            rule(1, FilterKind::Word, Some("banana"), FilterAction::Reject),
+            rule(2, FilterKind::Url, None, FilterAction::Reject),
+        ];
+        let outcome = apply_filters(&rules, "Nothing to see here");
+        assert_eq!(outcome.content, "Nothing to see here");
+        assert!(outcome.flags.is_empty());
+        assert!(outcome.rejection.is_none());
+    }
+
+    #[test]
+    fn words_match_whole_words_ignoring_case() {
+        let rules = [rule(1, FilterKind::Word, Some("ban"), FilterAction::Reject)];
+        assert!(apply_filters(&rules, "a BAN here").rejection.is_some());
+        assert!(apply_filters(&rules, "a banana here").rejection.is_none());
+    }
+
+    #[test]
+    fn words_with_symbols_at_the_edges_match() {
+        let rules = [
+            rule(1, FilterKind::Word, Some("c++"), FilterAction::Redact),
+            rule(2, FilterKind::Word, Some("@admins"), FilterAction::Redact),
+            rule(3, FilterKind::Word, Some(":skull:"), FilterAction::Redact),
+        ];
+        let outcome = apply_filters(&rules, "c++ pings @admins :skull:");
+        assert_eq!(outcome.content, "[redacted] pings [redacted] [redacted]");
+        assert_eq!(apply_filters(&rules, "abc++").content, "abc++");
+    }
+
+    #[test]
+    fn blank_patterns_match_nothing() {
+        let rules = [
+            rule(1, FilterKind::Word, Some("  "), FilterAction::Redact),
+            rule(2, FilterKind::Regex, Some(""), FilterAction::Reject),
+            rule(3, FilterKind::Word, None, FilterAction::Reject),
+        ];
+        let outcome = apply_filters(&rules, "anything at all");
+        assert_eq!(outcome.content, "anything at all");
+        assert!(outcome.flags.is_empty());
+        assert!(outcome.rejection.is_none());
+    }
+
+    #[test]
+    fn redaction_replaces_matches_and_flags() {
+        let rules = [rule(1, FilterKind::Invite, None, FilterAction::Redact)];
+        let outcome = apply_filters(&rules, "join discord.gg/abc now");
+        assert_eq!(outcome.content, "join [redacted] now");
+        assert_eq!(outcome.flags, vec!["Redacted an invite link".to_owned()]);
+        assert!(outcome.rejection.is_none());
+    }
+
+    #[test]
+    fn earlier_redaction_stops_later_rule() {
+        let rules = [
+            rule(1, FilterKind::Url, None, FilterAction::Redact),
+            rule(2, FilterKind::Invite, None, FilterAction::Reject),
+        ];
+        let outcome = apply_filters(&rules, "see https://discord.gg/abc");
+        assert_eq!(outcome.content, "see [redacted]");
+        assert!(outcome.rejection.is_none());
+    }
+
+    #[test]
+    fn later_order_lets_rejection_win() {
+        let rules = [
+            rule(1, FilterKind::Invite, None, FilterAction::Reject),
+            rule(2, FilterKind::Url, None, FilterAction::Redact),
+        ];
+        let outcome = apply_filters(&rules, "see https://discord.gg/abc");
+        assert_eq!(
+            outcome.rejection,
+            Some("it contains an invite link".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejection_stops_at_first_match() {
+        let rules = [
+            rule(1, FilterKind::Word, Some("first"), FilterAction::Flag),
+            rule(2, FilterKind::Word, Some("second"), FilterAction::Reject),
+            rule(3, FilterKind::Word, Some("third"), FilterAction::Flag),
+        ];
+        let outcome = apply_filters(&rules, "first second third");
+        assert_eq!(
+            outcome.flags,
+            vec!["Contains the banned word `first`".to_owned()]
+        );
+        assert_eq!(
+            outcome.rejection,
+            Some("it contains the banned word `second`".to_owned())
+        );
+    }
+
+    #[test]
+    fn max_lines_redacts_extra_lines() {
+        let rules = [rule(
+            1,
+            FilterKind::MaxLines,
+            Some("2"),
+            FilterAction::Redact,
+        )];
+        let outcome = apply_filters(&rules, "one\ntwo\nthree\nfour");
+        assert_eq!(outcome.content, "one\ntwo");
+        assert_eq!(outcome.flags, vec!["Redacted more than 2 lines".to_owned()]);
+        let short = apply_filters(&rules, "one\ntwo");
+        assert!(short.flags.is_empty());
+    }
+
+    #[test]
+    fn mass_mentions_and_zalgo() {
+        let rules = [
+            rule(1, FilterKind::MassMention, None, FilterAction::Flag),
+            rule(2, FilterKind::Zalgo, None, FilterAction::Flag),
+        ];
+        let outcome = apply_filters(&rules, "hey @everyone z\u{0301}\u{0302}\u{0303}");
+        assert_eq!(
+            outcome.flags,
+            vec![
+                "Contains a mass mention".to_owned(),
+                "Contains zalgo text".to_owned()
+            ]
+        );
+    }
+}
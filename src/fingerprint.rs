@@ -0,0 +1,105 @@
+use std::hash::Hasher;
+use twox_hash::XxHash64;
+
+const SHINGLE_WORDS: usize = 3;
+// Fingerprints this many bits apart or closer are treated as the same confession. A one word
+// edit to a paragraph moves up to about a dozen bits, unrelated text sits around half of the 64.
+pub const MAX_DUPLICATE_DISTANCE: u32 = 12;
+
+fn hash_shingle(words: &[String]) -> u64 {
+    let mut hasher = XxHash64::with_seed(0);
+    for word in words {
+        hasher.write(word.as_bytes());
+        hasher.write_u8(b' ');
+    }
+    hasher.finish()
+}
+
+// Simhash over word shingles: similar text gives fingerprints that differ in only a few bits.
+pub fn simhash(content: &str) -> u64 {
+    let words = content
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(|c| c.to_lowercase())
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<String>>();
+    if words.is_empty() {
+        return 0;
+    }
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let hash = hash_shingle(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0u64, |fingerprint, (bit, _)| fingerprint | (1 << bit))
+}
+
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFESSION: &str =
+        "I have been pretending to understand the group project for three weeks \
+        now and tomorrow is the presentation. Everyone thinks I wrote the database part but my \
+        cousin did it over the weekend while I played games and I feel terrible about it.";
+
+    #[test]
+    fn case_and_punctuation_are_ignored() {
+        let shouted = CONFESSION.to_uppercase().replace('.', "!!!");
+        assert_eq!(simhash(CONFESSION), simhash(&shouted));
+    }
+
+    #[test]
+    fn small_edit_is_a_near_duplicate() {
+        for edited in [
+            CONFESSION.replace("three weeks", "four weeks"),
+            CONFESSION.replace("terrible", "awful"),
+            format!("{} honestly", CONFESSION),
+        ] {
+            assert!(distance(simhash(CONFESSION), simhash(&edited)) <= MAX_DUPLICATE_DISTANCE);
+        }
+    }
+
+    #[test]
+    fn unrelated_text_is_far_apart() {
+        let unrelated = "My neighbour's cat visits every morning and I have secretly named \
+            him Sir Reginald. I buy him treats with my lunch money and leave the window open so \
+            he can sleep on the radiator until his owner gets home from work.";
+        assert!(distance(simhash(CONFESSION), simhash(unrelated)) > MAX_DUPLICATE_DISTANCE * 2);
+    }
+
+    #[test]
+    fn short_inputs() {
+        assert_eq!(simhash(""), 0);
+        assert_eq!(simhash("  ?! ... "), 0);
+        // Fewer words than a shingle still fingerprint, and the same words agree.
+        assert_ne!(simhash("hello"), 0);
+        assert_eq!(simhash("Hello there"), simhash("hello, there!"));
+        assert_ne!(simhash("hello there"), simhash("goodbye there"));
+    }
+
+    #[test]
+    fn distance_counts_differing_bits() {
+        assert_eq!(distance(0, 0), 0);
+        assert_eq!(distance(0b1011, 0b0001), 2);
+        assert_eq!(distance(0, u64::MAX), 64);
+    }
+}
@@ -1,7 +1,14 @@
+use std::sync::Arc;
+
 use poise::serenity_prelude as serenity;
 use shuttle_secrets::SecretStore;
 
+mod cache;
 mod commands;
+mod crypto;
+mod filter;
+mod fingerprint;
+mod media;
 mod router;
 use router::build_router;
 mod auth;
@@ -13,6 +20,8 @@ mod util;
 
 pub struct Data {
     database: sea_orm::DatabaseConnection,
+    author_key: crypto::AuthorKey,
+    autocomplete: Arc<cache::AutocompleteCache>,
 }
 pub struct BotService {
     discord_bot: poise::FrameworkBuilder<
@@ -54,6 +63,11 @@ async fn serenity(
         panic!("Error getting discord api key");
     }
     let discord_api_key = discord_api_key.unwrap();
+    let confession_key = secret_store.get("CONFESSION_KEY");
+    if confession_key.is_none() {
+        panic!("Error getting confession key");
+    }
+    let author_key = crypto::derive_key(&confession_key.unwrap());
 
     let discord_bot = poise::Framework::builder()
         .options(poise::FrameworkOptions {
@@ -63,17 +77,45 @@ async fn serenity(
                 commands::util::ping_vc(),
                 //
                 commands::channel::get_channels(),
+                commands::channel::set_channel_vetting(),
+                commands::channel::set_channel_content_warning(),
                 //
                 commands::confessions::confess(),
-                // TODO: Add autocomplete for this thing.
-                // commands::confessions::confess_to(),
+                commands::confessions::polls::confess_poll(),
+                commands::confessions::direct::confess_to_user(),
+                commands::confessions::direct::allow_direct_confessions(),
+                commands::confessions::confess_to(),
                 commands::confessions::set_vetting(),
                 commands::confessions::set_confessing(),
+                commands::confessions::ask::set_ask_me(),
+                commands::confessions::ask::questions(),
+                commands::confessions::modmail::set_modmail(),
+                commands::confessions::modmail::modmail_reply(),
+                commands::confessions::modmail::modmail_close(),
                 commands::confessions::vote_reveal(),
                 commands::confessions::shuffle(),
                 commands::confessions::lock_shuffle(),
+                commands::confessions::queue::queue(),
+                commands::confessions::deny::add_deny_reason(),
+                commands::confessions::deny::get_deny_reasons(),
+                commands::confessions::deny::remove_deny_reason(),
+                commands::confessions::limits::set_rate_limit(),
+                commands::confessions::limits::get_rate_limits(),
+                commands::confessions::limits::remove_rate_limit(),
+                commands::confessions::blocks::get_blocks(),
+                commands::confessions::blocks::unblock(),
+                commands::confessions::filters::add_filter(),
+                commands::confessions::filters::get_filters(),
+                commands::confessions::filters::remove_filter(),
                 //
                 commands::guild::set_mod_role(),
+                commands::guild::set_reply_vetting(),
+                commands::guild::set_vetting_quorum(),
+                commands::guild::set_pending_expiry(),
+                commands::guild::set_trusted(),
+                commands::guild::set_duplicate_action(),
+                commands::guild::set_attachment_rules(),
+                commands::guild::set_posting_schedule(),
                 // subjects
                 commands::subjects::add_subject(),
                 commands::subjects::get_subjects(),
@@ -81,7 +123,8 @@ async fn serenity(
                 commands::subjects::add_user_subjects(),
                 commands::subjects::get_user_subjects(),
                 commands::subjects::remove_user_subjects(),
-                commands::subjects::get_users_with_subject()
+                commands::subjects::get_users_with_subject(),
+                commands::subjects::subject_notifications()
             ],
             prefix_options: poise::PrefixFrameworkOptions {
                 prefix: Some(".".into()),
@@ -100,11 +143,40 @@ async fn serenity(
         .intents(
             serenity::GatewayIntents::privileged().union(serenity::GatewayIntents::non_privileged()),
         )
-        .setup(|_ctx, _ready, _framework| {
+        .setup(move |ctx, ready, _framework| {
             Box::pin(async move {
                 // poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                let database = database::connect().await.unwrap();
+                let autocomplete = Arc::new(cache::AutocompleteCache::new());
+                tokio::spawn(commands::confessions::queue::expire_pending_confessions(
+                    ctx.clone(),
+                    ready.user.id,
+                    Data {
+                        database: database.clone(),
+                        author_key,
+                        autocomplete: autocomplete.clone(),
+                    },
+                ));
+                tokio::spawn(commands::confessions::schedule::post_scheduled_confessions(
+                    ctx.clone(),
+                    Data {
+                        database: database.clone(),
+                        author_key,
+                        autocomplete: autocomplete.clone(),
+                    },
+                ));
+                tokio::spawn(commands::confessions::polls::close_polls(
+                    ctx.clone(),
+                    Data {
+                        database: database.clone(),
+                        author_key,
+                        autocomplete: autocomplete.clone(),
+                    },
+                ));
                 Ok(Data {
-                    database: database::connect().await.unwrap(),
+                    database,
+                    author_key,
+                    autocomplete,
                 })
             })
         });
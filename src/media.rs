@@ -0,0 +1,611 @@
+use std::{borrow::Cow, io::Cursor};
+
+use anyhow::{anyhow, Result};
+use image::{
+    codecs::gif::{GifDecoder, GifEncoder, Repeat},
+    imageops::FilterType,
+    io::{Limits, Reader},
+    AnimationDecoder, DynamicImage, ImageDecoder, ImageFormat, ImageOutputFormat,
+};
+use poise::serenity_prelude as serenity;
+
+use crate::entity::{confession_attachments, guild};
+
+// The confession commands have this many attachment options.
+pub const MAX_ATTACHMENTS: u32 = 4;
+// Discord's upload limit for bots in servers without boosts.
+pub const MAX_ATTACHMENT_MB: u32 = 25;
+const MAX_IMAGE_DIMENSION: u32 = 4096;
+// Large enough for full resolution phone photos, which are scaled down after decoding.
+const MAX_DECODE_DIMENSION: u32 = 12_000;
+const MAX_DECODE_ALLOC: u64 = 512 * 1024 * 1024;
+// Every GIF frame decodes to a full canvas, so small files can still expand enormously.
+const MAX_GIF_FRAMES: usize = 500;
+const MAX_GIF_DECODED_BYTES: usize = 256 * 1024 * 1024;
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+// Boxes that only group other boxes, which are searched for metadata.
+const ISO_CONTAINER_BOXES: [[u8; 4]; 4] = [*b"moov", *b"trak", *b"mdia", *b"minf"];
+// User data, where phones keep the GPS location and device model.
+const ISO_METADATA_BOXES: [[u8; 4]; 2] = [*b"udta", *b"meta"];
+// Headers recording when the file was made.
+const ISO_TIMESTAMP_BOXES: [[u8; 4]; 3] = [*b"mvhd", *b"tkhd", *b"mdhd"];
+const JPEG_QUALITY: u8 = 90;
+
+#[derive(Debug, Clone)]
+pub struct CleanImage {
+    pub bytes: Vec<u8>,
+    pub extension: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttachmentRules {
+    pub max_attachments: u32,
+    pub max_attachment_mb: u32,
+    pub allow_video: bool,
+    pub allow_audio: bool,
+    pub other_types: Vec<String>,
+}
+
+impl AttachmentRules {
+    pub fn from_guild(guild: Option<&guild::Model>) -> AttachmentRules {
+        match guild {
+            Some(guild) => AttachmentRules {
+                max_attachments: guild.max_attachments,
+                max_attachment_mb: guild.max_attachment_mb,
+                allow_video: guild.allow_video == 1,
+                allow_audio: guild.allow_audio == 1,
+                other_types: parse_types(guild.allowed_file_types.as_deref().unwrap_or("")),
+            },
+            None => AttachmentRules {
+                max_attachments: 1,
+                max_attachment_mb: 8,
+                allow_video: false,
+                allow_audio: false,
+                other_types: vec![],
+            },
+        }
+    }
+
+    fn allows(&self, content_type: &str) -> bool {
+        content_type.starts_with("image/")
+            || (self.allow_video && content_type.starts_with("video/"))
+            || (self.allow_audio && content_type.starts_with("audio/"))
+            || self
+                .other_types
+                .iter()
+                .any(|allowed| type_matches(allowed, content_type))
+    }
+
+    pub fn describe(&self) -> String {
+        let mut types = vec!["images".to_owned()];
+        if self.allow_video {
+            types.push("video".to_owned());
+        }
+        if self.allow_audio {
+            types.push("audio".to_owned());
+        }
+        types.extend(self.other_types.iter().cloned());
+        let mut description = format!(
+            "Up to {} file(s) of at most {} MB each. Allowed: {}",
+            self.max_attachments,
+            self.max_attachment_mb,
+            types.join(", ")
+        );
+        if types.len() > 1 {
+            description.push_str(
+                "\nMetadata is only removed from images and MP4, MOV and M4A files. \
+                Other files are posted as they were uploaded.",
+            );
+        }
+        description
+    }
+}
+
+// Comma separated MIME types, where `type/*` matches every subtype.
+pub fn parse_types(types: &str) -> Vec<String> {
+    types
+        .split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn type_matches(allowed: &str, content_type: &str) -> bool {
+    let content_type = content_type.split(';').next().unwrap_or("").trim();
+    match allowed.strip_suffix("/*") {
+        Some(prefix) => content_type
+            .split('/')
+            .next()
+            .map(|t| t == prefix)
+            .unwrap_or(false),
+        None => allowed == content_type,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfessionFile {
+    pub filename: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+    pub spoiler: bool,
+}
+
+impl ConfessionFile {
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+
+    // Images and ISO media files are cleaned when they're downloaded, anything else isn't.
+    pub fn keeps_metadata(&self) -> bool {
+        !self.is_image() && !is_iso_media(&self.bytes)
+    }
+
+    // Discord blurs attachments whose names start with `SPOILER_`.
+    pub fn upload_name(&self) -> String {
+        match self.spoiler {
+            true => format!("SPOILER_{}", self.filename),
+            false => self.filename.clone(),
+        }
+    }
+
+    pub fn to_attachment(&self) -> serenity::AttachmentType<'_> {
+        serenity::AttachmentType::Bytes {
+            data: Cow::Borrowed(&self.bytes),
+            filename: self.upload_name(),
+        }
+    }
+}
+
+impl From<confession_attachments::Model> for ConfessionFile {
+    fn from(attachment: confession_attachments::Model) -> Self {
+        ConfessionFile {
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            bytes: attachment.data,
+            spoiler: attachment.spoiler == 1,
+        }
+    }
+}
+
+// Spoilered images stay as plain attachments, since an embed image can't be blurred.
+pub fn embed_image(files: &[ConfessionFile]) -> Option<String> {
+    files
+        .iter()
+        .find(|file| file.is_image() && !file.spoiler)
+        .map(|file| format!("attachment://{}", file.upload_name()))
+}
+
+fn cap_size(image: DynamicImage) -> DynamicImage {
+    if image.width() > MAX_IMAGE_DIMENSION || image.height() > MAX_IMAGE_DIMENSION {
+        image.resize(
+            MAX_IMAGE_DIMENSION,
+            MAX_IMAGE_DIMENSION,
+            FilterType::Triangle,
+        )
+    } else {
+        image
+    }
+}
+
+fn decode_limits(max_dimension: u32) -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dimension);
+    limits.max_image_height = Some(max_dimension);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    limits
+}
+
+fn read_u16(bytes: &[u8], at: usize, little_endian: bool) -> Option<u16> {
+    let pair = [*bytes.get(at)?, *bytes.get(at + 1)?];
+    Some(match little_endian {
+        true => u16::from_le_bytes(pair),
+        false => u16::from_be_bytes(pair),
+    })
+}
+
+fn read_u32(bytes: &[u8], at: usize, little_endian: bool) -> Option<u32> {
+    let quad = [
+        *bytes.get(at)?,
+        *bytes.get(at + 1)?,
+        *bytes.get(at + 2)?,
+        *bytes.get(at + 3)?,
+    ];
+    Some(match little_endian {
+        true => u32::from_le_bytes(quad),
+        false => u32::from_be_bytes(quad),
+    })
+}
+
+// Looks for the Orientation tag in the first IFD of a TIFF block, which is how EXIF is stored.
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let little_endian = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    if read_u16(tiff, 2, little_endian)? != 42 {
+        return None;
+    }
+    let ifd = read_u32(tiff, 4, little_endian)? as usize;
+    let entries = read_u16(tiff, ifd, little_endian)? as usize;
+    (0..entries)
+        .map(|entry| ifd + 2 + entry * 12)
+        .find(|&entry| read_u16(tiff, entry, little_endian) == Some(EXIF_ORIENTATION_TAG))
+        .and_then(|entry| read_u16(tiff, entry + 8, little_endian))
+        .filter(|orientation| (1..=8).contains(orientation))
+}
+
+fn strip_exif_header(exif: &[u8]) -> &[u8] {
+    exif.strip_prefix(b"Exif\0\0").unwrap_or(exif)
+}
+
+// Phones usually save photos sideways and record how to turn them in the EXIF Orientation tag.
+fn exif_orientation(bytes: &[u8], format: ImageFormat) -> Option<u16> {
+    match format {
+        ImageFormat::Jpeg => {
+            let mut at = 2;
+            while *bytes.get(at)? == 0xFF {
+                let marker = *bytes.get(at + 1)?;
+                // Image data starts at SOS, and metadata always comes before it.
+                if marker == 0xDA || marker == 0xD9 {
+                    return None;
+                }
+                let length = read_u16(bytes, at + 2, false)? as usize;
+                let segment = bytes.get(at + 4..at + 2 + length)?;
+                if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+                    return tiff_orientation(strip_exif_header(segment));
+                }
+                at += 2 + length;
+            }
+            None
+        }
+        ImageFormat::Png => {
+            let mut at = 8;
+            while at + 8 <= bytes.len() {
+                let length = read_u32(bytes, at, false)? as usize;
+                let chunk = bytes.get(at + 8..at + 8 + length)?;
+                if bytes.get(at + 4..at + 8)? == b"eXIf" {
+                    return tiff_orientation(chunk);
+                }
+                at += 12 + length;
+            }
+            None
+        }
+        ImageFormat::WebP => {
+            let mut at = 12;
+            while at + 8 <= bytes.len() {
+                let length = read_u32(bytes, at + 4, true)? as usize;
+                let chunk = bytes.get(at + 8..at + 8 + length)?;
+                if bytes.get(at..at + 4)? == b"EXIF" {
+                    return tiff_orientation(strip_exif_header(chunk));
+                }
+                at += 8 + length + length % 2;
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+// The pixels are turned the way the tag says, since the tag itself is dropped with the rest of the metadata.
+fn apply_orientation(image: DynamicImage, orientation: Option<u16>) -> DynamicImage {
+    match orientation {
+        Some(2) => image.fliph(),
+        Some(3) => image.rotate180(),
+        Some(4) => image.flipv(),
+        Some(5) => image.rotate90().fliph(),
+        Some(6) => image.rotate90(),
+        Some(7) => image.rotate270().fliph(),
+        Some(8) => image.rotate270(),
+        _ => image,
+    }
+}
+
+fn reencode_gif(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = GifDecoder::new(Cursor::new(bytes))?;
+    decoder.set_limits(decode_limits(MAX_IMAGE_DIMENSION))?;
+    let mut frames = vec![];
+    let mut decoded_bytes = 0;
+    for frame in decoder.into_frames() {
+        let frame = frame?;
+        decoded_bytes += frame.buffer().len();
+        if frames.len() >= MAX_GIF_FRAMES || decoded_bytes > MAX_GIF_DECODED_BYTES {
+            return Err(anyhow!("the GIF has too many frames or is too large"));
+        }
+        frames.push(frame);
+    }
+    let mut out = vec![];
+    {
+        let mut encoder = GifEncoder::new(&mut out);
+        encoder.set_repeat(Repeat::Infinite)?;
+        encoder.encode_frames(frames)?;
+    }
+    Ok(out)
+}
+
+// Decoding to pixels and encoding again drops EXIF, GPS and any other metadata the file carried.
+pub fn clean_image(bytes: &[u8]) -> Result<CleanImage> {
+    let format = match image::guess_format(bytes) {
+        Ok(format) => format,
+        Err(_) => return Err(anyhow!("That file isn't a supported image.")),
+    };
+    if format == ImageFormat::Gif {
+        return match reencode_gif(bytes) {
+            Ok(bytes) => Ok(CleanImage {
+                bytes,
+                extension: "gif",
+            }),
+            Err(e) => Err(anyhow!("Error reading image: {}", e)),
+        };
+    }
+    let (output_format, extension) = match format {
+        ImageFormat::Png => (ImageOutputFormat::Png, "png"),
+        ImageFormat::Jpeg => (ImageOutputFormat::Jpeg(JPEG_QUALITY), "jpg"),
+        // There's no pure Rust WebP encoder here, so these are kept lossless as PNG.
+        ImageFormat::WebP => (ImageOutputFormat::Png, "png"),
+        _ => {
+            return Err(anyhow!(
+                "Only PNG, JPEG, GIF and WebP images can be confessed."
+            ))
+        }
+    };
+    let mut reader = Reader::with_format(Cursor::new(bytes), format);
+    reader.limits(decode_limits(MAX_DECODE_DIMENSION));
+    let decoded = match reader.decode() {
+        Ok(decoded) => apply_orientation(cap_size(decoded), exif_orientation(bytes, format)),
+        Err(e) => return Err(anyhow!("Error reading image: {}", e)),
+    };
+    // JPEG can't hold an alpha channel.
+    let decoded = match output_format {
+        ImageOutputFormat::Jpeg(_) => DynamicImage::ImageRgb8(decoded.to_rgb8()),
+        _ => decoded,
+    };
+    let mut out = Cursor::new(vec![]);
+    match decoded.write_to(&mut out, output_format) {
+        Ok(_) => Ok(CleanImage {
+            bytes: out.into_inner(),
+            extension,
+        }),
+        Err(e) => Err(anyhow!("Error re-encoding image: {}", e)),
+    }
+}
+
+// MP4, MOV and M4A files are ISO base media files, a tree of length prefixed boxes.
+fn is_iso_media(bytes: &[u8]) -> bool {
+    bytes.get(4..8) == Some(b"ftyp")
+}
+
+// Returns the header and total length of the box at `at`.
+fn iso_box_size(bytes: &[u8], at: usize) -> Option<(usize, usize)> {
+    let (header, size) = match read_u32(bytes, at, false)? {
+        // Runs to the end of the file.
+        0 => (8, bytes.len() - at),
+        1 => {
+            let large = (read_u32(bytes, at + 8, false)? as u64) << 32
+                | read_u32(bytes, at + 12, false)? as u64;
+            (16, usize::try_from(large).ok()?)
+        }
+        size => (8, size as usize),
+    };
+    match size >= header && at.checked_add(size)? <= bytes.len() {
+        true => Some((header, size)),
+        false => None,
+    }
+}
+
+// Metadata boxes are zeroed and renamed to `free`, which players skip. Nothing changes length,
+// so the offsets pointing into the media data stay right.
+fn strip_iso_metadata(bytes: &mut [u8]) {
+    let mut at = 0;
+    while at + 8 <= bytes.len() {
+        let (header, size) = match iso_box_size(bytes, at) {
+            Some(box_size) => box_size,
+            None => return,
+        };
+        let kind = <[u8; 4]>::try_from(&bytes[at + 4..at + 8]).unwrap_or_default();
+        if ISO_CONTAINER_BOXES.contains(&kind) {
+            strip_iso_metadata(&mut bytes[at + header..at + size]);
+        } else if ISO_METADATA_BOXES.contains(&kind) {
+            bytes[at + 4..at + 8].copy_from_slice(b"free");
+            bytes[at + header..at + size].fill(0);
+        } else if ISO_TIMESTAMP_BOXES.contains(&kind) {
+            // Version 1 headers use 64 bit creation and modification times.
+            let body = &mut bytes[at + header..at + size];
+            let width = match body.first() {
+                Some(1) => 8,
+                _ => 4,
+            };
+            if let Some(times) = body.get_mut(4..4 + 2 * width) {
+                times.fill(0);
+            }
+        }
+        at += size;
+    }
+}
+
+// Files are renamed so the original name can't give the confessor away.
+fn anonymous_filename(attachment: &serenity::Attachment, position: usize) -> String {
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .filter(|extension| {
+            !extension.is_empty()
+                && extension.len() <= 8
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    match extension {
+        Some(extension) => format!("file{}.{}", position + 1, extension),
+        None => format!("file{}", position + 1),
+    }
+}
+
+pub async fn download_attachment(
+    attachment: &serenity::Attachment,
+    rules: &AttachmentRules,
+    position: usize,
+    spoiler: bool,
+) -> Result<ConfessionFile> {
+    let content_type = attachment
+        .content_type
+        .clone()
+        .unwrap_or("application/octet-stream".to_owned());
+    if !rules.allows(&content_type) {
+        return Err(anyhow!(
+            "{} ({}) isn't a file type this server accepts.",
+            attachment.filename,
+            content_type
+        ));
+    }
+    // Checked before downloading so oversized files aren't fetched at all.
+    let max_bytes = rules.max_attachment_mb.min(MAX_ATTACHMENT_MB) as u64 * 1024 * 1024;
+    if attachment.size > max_bytes {
+        return Err(anyhow!(
+            "{} is larger than {} MB.",
+            attachment.filename,
+            rules.max_attachment_mb
+        ));
+    }
+    let mut bytes = match attachment.download().await {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(anyhow!("Error downloading {}: {}", attachment.filename, e)),
+    };
+    if content_type.starts_with("image/") {
+        // Decoding is CPU bound, so it's kept off the async runtime.
+        let image = match tokio::task::spawn_blocking(move || clean_image(&bytes)).await {
+            Ok(cleaned) => cleaned?,
+            Err(e) => return Err(anyhow!("Error reading image: {}", e)),
+        };
+        return Ok(ConfessionFile {
+            filename: format!("image{}.{}", position + 1, image.extension),
+            content_type: match image.extension {
+                "jpg" => "image/jpeg",
+                "gif" => "image/gif",
+                _ => "image/png",
+            }
+            .to_owned(),
+            bytes: image.bytes,
+            spoiler,
+        });
+    }
+    if is_iso_media(&bytes) {
+        strip_iso_metadata(&mut bytes);
+    }
+    // Anything else can't be cleaned here, so it's passed on as it is.
+    Ok(ConfessionFile {
+        filename: anonymous_filename(attachment, position),
+        content_type,
+        bytes,
+        spoiler,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage};
+
+    // A TIFF block with one IFD entry holding the orientation.
+    fn tiff(orientation: u16, little_endian: bool) -> Vec<u8> {
+        let mut tiff = vec![];
+        let u16_bytes = |n: u16| match little_endian {
+            true => n.to_le_bytes(),
+            false => n.to_be_bytes(),
+        };
+        let u32_bytes = |n: u32| match little_endian {
+            true => n.to_le_bytes(),
+            false => n.to_be_bytes(),
+        };
+        tiff.extend(if little_endian { b"II" } else { b"MM" });
+        tiff.extend(u16_bytes(42));
+        tiff.extend(u32_bytes(8));
+        tiff.extend(u16_bytes(1));
+        tiff.extend(u16_bytes(EXIF_ORIENTATION_TAG));
+        tiff.extend(u16_bytes(3));
+        tiff.extend(u32_bytes(1));
+        tiff.extend(u16_bytes(orientation));
+        tiff.extend([0, 0]);
+        tiff.extend(u32_bytes(0));
+        tiff
+    }
+
+    #[test]
+    fn reads_orientation_in_either_byte_order() {
+        assert_eq!(tiff_orientation(&tiff(6, true)), Some(6));
+        assert_eq!(tiff_orientation(&tiff(8, false)), Some(8));
+        assert_eq!(tiff_orientation(&tiff(9, true)), None);
+        assert_eq!(tiff_orientation(b"II"), None);
+    }
+
+    #[test]
+    fn finds_orientation_in_jpeg_app1() {
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend(tiff(6, false));
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xE1];
+        jpeg.extend(((app1.len() + 2) as u16).to_be_bytes());
+        jpeg.extend(app1);
+        jpeg.extend([0xFF, 0xDA]);
+        assert_eq!(exif_orientation(&jpeg, ImageFormat::Jpeg), Some(6));
+        assert_eq!(exif_orientation(&jpeg[..12], ImageFormat::Jpeg), None);
+    }
+
+    #[test]
+    fn turns_pixels_upright() {
+        // 2x1: red on the left, blue on the right.
+        let mut pixels = RgbImage::new(2, 1);
+        pixels.put_pixel(0, 0, Rgb([255, 0, 0]));
+        pixels.put_pixel(1, 0, Rgb([0, 0, 255]));
+        let image = DynamicImage::ImageRgb8(pixels);
+        let rotated = apply_orientation(image.clone(), Some(6));
+        assert_eq!(rotated.dimensions(), (1, 2));
+        assert_eq!(rotated.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        let transposed = apply_orientation(image.clone(), Some(5));
+        assert_eq!(transposed.get_pixel(0, 1).0, [0, 0, 255, 255]);
+        assert_eq!(apply_orientation(image.clone(), None), image);
+    }
+
+    fn iso_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut iso_box = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        iso_box.extend(kind);
+        iso_box.extend(body);
+        iso_box
+    }
+
+    #[test]
+    fn strips_video_metadata_in_place() {
+        let mut mvhd = vec![0, 0, 0, 0];
+        mvhd.extend([0x11; 8]);
+        mvhd.extend([0x22; 8]);
+        let location = iso_box(b"\xa9xyz", b"+51.5074-000.1278/");
+        let mut moov = iso_box(b"mvhd", &mvhd);
+        moov.extend(iso_box(b"udta", &location));
+        let mut video = iso_box(b"ftyp", b"qt  ");
+        video.extend(iso_box(b"moov", &moov));
+        video.extend(iso_box(b"mdat", b"frames"));
+        let original = video.clone();
+        strip_iso_metadata(&mut video);
+
+        assert_eq!(video.len(), original.len());
+        let moov_at = 12;
+        let udta_at = moov_at + 8 + 8 + mvhd.len();
+        // Times zeroed, the rest of the header kept.
+        assert_eq!(video[moov_at + 20..moov_at + 28], [0; 8]);
+        assert_eq!(video[moov_at + 28..moov_at + 32], [0x22; 4]);
+        assert_eq!(&video[udta_at + 4..udta_at + 8], b"free");
+        assert!(video[udta_at + 8..udta_at + 8 + location.len()]
+            .iter()
+            .all(|byte| *byte == 0));
+        assert!(video.ends_with(&iso_box(b"mdat", b"frames")));
+        assert!(is_iso_media(&video));
+    }
+
+    #[test]
+    fn truncated_video_is_left_alone() {
+        let mut video = iso_box(b"ftyp", b"qt  ");
+        video.extend(iso_box(b"udta", b"secret"));
+        video.truncate(video.len() - 2);
+        let original = video.clone();
+        strip_iso_metadata(&mut video);
+        assert_eq!(video, original);
+    }
+}
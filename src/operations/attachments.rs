@@ -0,0 +1,60 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set};
+
+use crate::entity::confession_attachments;
+
+pub async fn get_confession_attachments(
+    db: &DatabaseConnection,
+    confession_id: i32,
+) -> Result<Vec<confession_attachments::Model>> {
+    let found_attachments = confession_attachments::Entity::find()
+        .filter(confession_attachments::Column::ConfessionId.eq(confession_id))
+        .order_by_asc(confession_attachments::Column::Position)
+        .all(db)
+        .await;
+    match found_attachments {
+        Ok(attachments) => Ok(attachments),
+        Err(e) => Err(anyhow!("Error getting attachments from database: {:?}", e)),
+    }
+}
+
+pub async fn add_attachment(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    position: i32,
+    filename: String,
+    content_type: String,
+    data: Vec<u8>,
+    spoiler: bool,
+) -> Result<()> {
+    let this_attachment = confession_attachments::ActiveModel {
+        confession_id: Set(confession_id),
+        position: Set(position),
+        filename: Set(filename),
+        content_type: Set(content_type),
+        data: Set(data),
+        spoiler: Set(spoiler as i8),
+        ..Default::default()
+    };
+    let add_result = confession_attachments::Entity::insert(this_attachment)
+        .exec(db)
+        .await;
+    match add_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error adding attachment to database: {:?}", e)),
+    }
+}
+
+pub async fn remove_confession_attachments(
+    db: &DatabaseConnection,
+    confession_id: i32,
+) -> Result<()> {
+    let remove_result = confession_attachments::Entity::delete_many()
+        .filter(confession_attachments::Column::ConfessionId.eq(confession_id))
+        .exec(db)
+        .await;
+    match remove_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error removing attachments from database: {:?}", e)),
+    }
+}
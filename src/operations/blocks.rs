@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::entity::guild_blocks;
+
+fn is_active(now: chrono::DateTime<chrono::Utc>) -> Condition {
+    Condition::any()
+        .add(guild_blocks::Column::ExpiresAt.is_null())
+        .add(guild_blocks::Column::ExpiresAt.gt(now))
+}
+
+pub async fn get_active_block(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+) -> Result<Option<guild_blocks::Model>> {
+    let found_block = guild_blocks::Entity::find()
+        .filter(guild_blocks::Column::GuildId.eq(guild_id))
+        .filter(guild_blocks::Column::AuthorHash.eq(author_hash))
+        .filter(is_active(chrono::Utc::now()))
+        .one(db)
+        .await;
+    match found_block {
+        Ok(block) => Ok(block),
+        Err(e) => Err(anyhow!("Error getting block from database: {:?}", e)),
+    }
+}
+
+pub async fn get_guild_blocks(
+    db: &DatabaseConnection,
+    guild_id: u64,
+) -> Result<Vec<guild_blocks::Model>> {
+    let found_blocks = guild_blocks::Entity::find()
+        .filter(guild_blocks::Column::GuildId.eq(guild_id))
+        .filter(is_active(chrono::Utc::now()))
+        .order_by_asc(guild_blocks::Column::CreatedAt)
+        .all(db)
+        .await;
+    match found_blocks {
+        Ok(blocks) => Ok(blocks),
+        Err(e) => Err(anyhow!("Error getting blocks from database: {:?}", e)),
+    }
+}
+
+// Blocking an author again replaces their earlier block.
+pub async fn set_block(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: String,
+    confession_id: i32,
+    blocked_by: u64,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<()> {
+    let existing = guild_blocks::Entity::find()
+        .filter(guild_blocks::Column::GuildId.eq(guild_id))
+        .filter(guild_blocks::Column::AuthorHash.eq(author_hash.clone()))
+        .one(db)
+        .await;
+    let this_block = guild_blocks::ActiveModel {
+        guild_id: Set(guild_id),
+        author_hash: Set(author_hash),
+        confession_id: Set(confession_id),
+        blocked_by: Set(blocked_by),
+        expires_at: Set(expires_at),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    let result = match existing {
+        Ok(Some(block)) => guild_blocks::Entity::update(guild_blocks::ActiveModel {
+            id: Set(block.id),
+            ..this_block
+        })
+        .exec(db)
+        .await
+        .map(|_| ()),
+        Ok(None) => guild_blocks::Entity::insert(this_block)
+            .exec(db)
+            .await
+            .map(|_| ()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error setting block in database: {:?}", e)),
+    }
+}
+
+pub async fn remove_block(db: &DatabaseConnection, guild_id: u64, author_hash: &str) -> Result<()> {
+    let remove_result = guild_blocks::Entity::delete_many()
+        .filter(guild_blocks::Column::GuildId.eq(guild_id))
+        .filter(guild_blocks::Column::AuthorHash.eq(author_hash))
+        .exec(db)
+        .await;
+    match remove_result {
+        Ok(r) if r.rows_affected > 0 => Ok(()),
+        Ok(_) => Err(anyhow!("That confessor is not blocked.")),
+        Err(e) => Err(anyhow!("Error removing block from database: {:?}", e)),
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Result};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, InsertResult, QueryFilter, Set, sea_query::OnConflict};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, sea_query::Expr};
 
 use crate::entity::channels;
 use serde::{Deserialize, Serialize};
@@ -12,6 +12,10 @@ pub enum ChannelUse {
     Confession,
     #[name = "vetting"]
     Vetting,
+    #[name = "ask me"]
+    AskMe,
+    #[name = "modmail"]
+    Modmail,
 }
 
 impl Into<i32> for ChannelUse {
@@ -20,6 +24,8 @@ impl Into<i32> for ChannelUse {
             ChannelUse::None => 0,
             ChannelUse::Confession => 1,
             ChannelUse::Vetting => 2,
+            ChannelUse::AskMe => 3,
+            ChannelUse::Modmail => 4,
         }
     }
 }
@@ -30,6 +36,8 @@ impl From<i32> for ChannelUse {
             0 => ChannelUse::None,
             1 => ChannelUse::Confession,
             2 => ChannelUse::Vetting,
+            3 => ChannelUse::AskMe,
+            4 => ChannelUse::Modmail,
             _ => ChannelUse::None,
         }
     }
@@ -41,10 +49,50 @@ impl Into<sea_orm::Value> for ChannelUse {
             ChannelUse::None => 0,
             ChannelUse::Confession => 1,
             ChannelUse::Vetting => 2,
+            ChannelUse::AskMe => 3,
+            ChannelUse::Modmail => 4,
         }))
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum VettingMode {
+    #[name = "always"]
+    Always,
+    #[name = "never"]
+    Never,
+    #[name = "untrusted only"]
+    Untrusted,
+}
+
+impl From<VettingMode> for i32 {
+    fn from(mode: VettingMode) -> Self {
+        match mode {
+            VettingMode::Always => 0,
+            VettingMode::Never => 1,
+            VettingMode::Untrusted => 2,
+        }
+    }
+}
+
+impl From<i32> for VettingMode {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => VettingMode::Always,
+            1 => VettingMode::Never,
+            2 => VettingMode::Untrusted,
+            _ => VettingMode::Always,
+        }
+    }
+}
+
+impl From<VettingMode> for sea_orm::Value {
+    fn from(mode: VettingMode) -> Self {
+        let mode: i32 = mode.into();
+        sea_orm::Value::Int(Some(mode))
+    }
+}
+
 #[allow(dead_code)]
 pub async fn get_channels(db: &DatabaseConnection) -> Result<Vec<channels::Model>> {
     let channels = channels::Entity::find().all(db).await;
@@ -113,6 +161,7 @@ pub async fn add_channel_for_guild(
         id: Set(channel_id),
         guild_id: Set(guild_id),
         channel_use: Set(channel_use.into()),
+        ..Default::default()
     };
     let add_result = channels::Entity::update(this_channel.clone())
         .exec(db)
@@ -122,3 +171,129 @@ pub async fn add_channel_for_guild(
         Err(e) => Err(anyhow!("Error adding channel to database: {:?}", e)),
     }
 }
+
+pub async fn get_channel_vetting_mode(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+) -> Result<VettingMode> {
+    let found_channel = channels::Entity::find_by_id(channel_id)
+        .filter(channels::Column::GuildId.eq(guild_id))
+        .one(db)
+        .await;
+    match found_channel {
+        Ok(channel) => Ok(channel
+            .map(|channel| channel.vetting_mode.into())
+            .unwrap_or(VettingMode::Always)),
+        Err(e) => Err(anyhow!("Error getting channel from database: {:?}", e)),
+    }
+}
+
+pub async fn set_channel_vetting_mode(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+    vetting_mode: VettingMode,
+) -> Result<()> {
+    let set_result = channels::Entity::update_many()
+        .col_expr(channels::Column::VettingMode, Expr::value(vetting_mode))
+        .filter(channels::Column::Id.eq(channel_id))
+        .filter(channels::Column::GuildId.eq(guild_id))
+        .exec(db)
+        .await;
+    match set_result {
+        Ok(r) if r.rows_affected > 0 => Ok(()),
+        Ok(_) => Err(anyhow!("This channel has no confession settings yet.")),
+        Err(e) => Err(anyhow!("Error setting channel in database: {:?}", e)),
+    }
+}
+
+pub async fn get_channel_requires_content_warning(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+) -> Result<bool> {
+    let found_channel = channels::Entity::find_by_id(channel_id)
+        .filter(channels::Column::GuildId.eq(guild_id))
+        .one(db)
+        .await;
+    match found_channel {
+        Ok(channel) => Ok(channel
+            .map(|channel| channel.require_content_warning == 1)
+            .unwrap_or(false)),
+        Err(e) => Err(anyhow!("Error getting channel from database: {:?}", e)),
+    }
+}
+
+pub async fn set_channel_requires_content_warning(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+    required: bool,
+) -> Result<()> {
+    let set_result = channels::Entity::update_many()
+        .col_expr(
+            channels::Column::RequireContentWarning,
+            Expr::value(required as i8),
+        )
+        .filter(channels::Column::Id.eq(channel_id))
+        .filter(channels::Column::GuildId.eq(guild_id))
+        .exec(db)
+        .await;
+    match set_result {
+        Ok(r) if r.rows_affected > 0 => Ok(()),
+        Ok(_) => Err(anyhow!("This channel has no confession settings yet.")),
+        Err(e) => Err(anyhow!("Error setting channel in database: {:?}", e)),
+    }
+}
+
+pub async fn get_channel_host(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+) -> Result<Option<u64>> {
+    let found_channel = channels::Entity::find_by_id(channel_id)
+        .filter(channels::Column::GuildId.eq(guild_id))
+        .one(db)
+        .await;
+    match found_channel {
+        Ok(channel) => Ok(channel.and_then(|channel| channel.host_id)),
+        Err(e) => Err(anyhow!("Error getting channel from database: {:?}", e)),
+    }
+}
+
+pub async fn set_channel_host(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+    host_id: u64,
+) -> Result<()> {
+    let set_result = channels::Entity::update_many()
+        .col_expr(channels::Column::HostId, Expr::value(host_id))
+        .filter(channels::Column::Id.eq(channel_id))
+        .filter(channels::Column::GuildId.eq(guild_id))
+        .exec(db)
+        .await;
+    match set_result {
+        Ok(r) if r.rows_affected > 0 => Ok(()),
+        Ok(_) => Err(anyhow!("This channel has no confession settings yet.")),
+        Err(e) => Err(anyhow!("Error setting channel in database: {:?}", e)),
+    }
+}
+
+pub async fn get_hosted_channels(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    host_id: u64,
+) -> Result<Vec<channels::Model>> {
+    let found_channels = channels::Entity::find()
+        .filter(channels::Column::GuildId.eq(guild_id))
+        .filter(channels::Column::ChannelUse.eq(ChannelUse::AskMe))
+        .filter(channels::Column::HostId.eq(host_id))
+        .all(db)
+        .await;
+    match found_channels {
+        Ok(channels) => Ok(channels),
+        Err(e) => Err(anyhow!("Error getting channels from database: {:?}", e)),
+    }
+}
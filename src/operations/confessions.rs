@@ -0,0 +1,538 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, SqlErr,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{entity::confessions, fingerprint};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ConfessionStatus {
+    Pending,
+    Approved,
+    Denied,
+    Skipped,
+}
+
+impl From<ConfessionStatus> for i32 {
+    fn from(status: ConfessionStatus) -> Self {
+        match status {
+            ConfessionStatus::Pending => 0,
+            ConfessionStatus::Approved => 1,
+            ConfessionStatus::Denied => 2,
+            ConfessionStatus::Skipped => 3,
+        }
+    }
+}
+
+impl From<i32> for ConfessionStatus {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => ConfessionStatus::Pending,
+            1 => ConfessionStatus::Approved,
+            2 => ConfessionStatus::Denied,
+            3 => ConfessionStatus::Skipped,
+            _ => ConfessionStatus::Pending,
+        }
+    }
+}
+
+impl From<ConfessionStatus> for sea_orm::Value {
+    fn from(status: ConfessionStatus) -> Self {
+        let status: i32 = status.into();
+        sea_orm::Value::Int(Some(status))
+    }
+}
+
+impl std::fmt::Display for ConfessionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfessionStatus::Pending => write!(f, "pending"),
+            ConfessionStatus::Approved => write!(f, "approved"),
+            ConfessionStatus::Denied => write!(f, "denied"),
+            ConfessionStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+pub async fn get_confession(
+    db: &DatabaseConnection,
+    id: i32,
+) -> Result<Option<confessions::Model>> {
+    match confessions::Entity::find_by_id(id).one(db).await {
+        Ok(c) => Ok(c),
+        Err(e) => Err(anyhow!("Error getting confession from database: {:?}", e)),
+    }
+}
+
+pub async fn get_confession_by_number(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    number: u64,
+) -> Result<Option<confessions::Model>> {
+    let found_confession = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .filter(confessions::Column::Number.eq(number))
+        .one(db)
+        .await;
+    match found_confession {
+        Ok(c) => Ok(c),
+        Err(e) => Err(anyhow!("Error getting confession from database: {:?}", e)),
+    }
+}
+
+// Oldest first, optionally only those created before `before`.
+pub async fn get_pending_confessions(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    before: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Vec<confessions::Model>> {
+    let mut query = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .filter(confessions::Column::Status.eq(ConfessionStatus::Pending));
+    if let Some(before) = before {
+        query = query.filter(confessions::Column::CreatedAt.lt(before));
+    }
+    let found_confessions = query
+        .order_by_asc(confessions::Column::CreatedAt)
+        .all(db)
+        .await;
+    match found_confessions {
+        Ok(c) => Ok(c),
+        Err(e) => Err(anyhow!("Error getting confessions from database: {:?}", e)),
+    }
+}
+
+async fn count_author_confessions(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+    status: ConfessionStatus,
+) -> Result<u64> {
+    let counted = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .filter(confessions::Column::AuthorHash.eq(author_hash))
+        .filter(confessions::Column::Status.eq(status))
+        .count(db)
+        .await;
+    match counted {
+        Ok(count) => Ok(count),
+        Err(e) => Err(anyhow!("Error counting confessions in database: {:?}", e)),
+    }
+}
+
+// (approved, denied) confessions by this author in the guild.
+pub async fn get_author_history(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+) -> Result<(u64, u64)> {
+    let approved =
+        count_author_confessions(db, guild_id, author_hash, ConfessionStatus::Approved).await?;
+    let denied =
+        count_author_confessions(db, guild_id, author_hash, ConfessionStatus::Denied).await?;
+    Ok((approved, denied))
+}
+
+// Newest first, skipping confessions from before fingerprints were stored.
+pub async fn get_fingerprinted_confessions_since(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<confessions::Model>> {
+    let found_confessions = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .filter(confessions::Column::CreatedAt.gte(since))
+        .filter(confessions::Column::Fingerprint.ne(0))
+        .order_by_desc(confessions::Column::CreatedAt)
+        .all(db)
+        .await;
+    match found_confessions {
+        Ok(c) => Ok(c),
+        Err(e) => Err(anyhow!("Error getting confessions from database: {:?}", e)),
+    }
+}
+
+// Oldest first. Only counts confessions to `channel_id` if one is given.
+pub async fn get_author_confessions_since(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+    channel_id: Option<u64>,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<confessions::Model>> {
+    let mut query = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .filter(confessions::Column::AuthorHash.eq(author_hash))
+        .filter(confessions::Column::CreatedAt.gte(since));
+    if let Some(channel_id) = channel_id {
+        query = query.filter(confessions::Column::ChannelId.eq(channel_id));
+    }
+    let found_confessions = query
+        .order_by_asc(confessions::Column::CreatedAt)
+        .all(db)
+        .await;
+    match found_confessions {
+        Ok(c) => Ok(c),
+        Err(e) => Err(anyhow!("Error getting confessions from database: {:?}", e)),
+    }
+}
+
+pub async fn count_author_pending(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+    channel_id: Option<u64>,
+) -> Result<u64> {
+    let mut query = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .filter(confessions::Column::AuthorHash.eq(author_hash))
+        .filter(confessions::Column::Status.eq(ConfessionStatus::Pending));
+    if let Some(channel_id) = channel_id {
+        query = query.filter(confessions::Column::ChannelId.eq(channel_id));
+    }
+    match query.count(db).await {
+        Ok(count) => Ok(count),
+        Err(e) => Err(anyhow!("Error counting confessions in database: {:?}", e)),
+    }
+}
+
+// Newest first, for suggesting confessions to moderators.
+pub async fn get_recent_confessions(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    limit: u64,
+) -> Result<Vec<confessions::Model>> {
+    let found_confessions = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .order_by_desc(confessions::Column::Number)
+        .limit(limit)
+        .all(db)
+        .await;
+    match found_confessions {
+        Ok(c) => Ok(c),
+        Err(e) => Err(anyhow!("Error getting confessions from database: {:?}", e)),
+    }
+}
+
+pub async fn get_next_confession_number(db: &DatabaseConnection, guild_id: u64) -> Result<u64> {
+    let last_confession = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .order_by_desc(confessions::Column::Number)
+        .one(db)
+        .await;
+    match last_confession {
+        Ok(last) => Ok(last.map(|c| c.number + 1).unwrap_or(1)),
+        Err(e) => Err(anyhow!("Error getting confession from database: {:?}", e)),
+    }
+}
+
+// Numbers are taken as max + 1, so two confessions arriving together can pick the same one.
+const MAX_NUMBER_ATTEMPTS: u32 = 5;
+
+// The unique guild/number index rejects the loser of a race, which then takes the next number.
+pub async fn add_confession(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+    author_ref: String,
+    author_hash: String,
+    pseudonym: u32,
+    content: String,
+) -> Result<confessions::Model> {
+    let fingerprint = fingerprint::simhash(&content);
+    for _ in 0..MAX_NUMBER_ATTEMPTS {
+        let number = get_next_confession_number(db, guild_id).await?;
+        let now = chrono::Utc::now();
+        let this_confession = confessions::ActiveModel {
+            guild_id: Set(guild_id),
+            number: Set(number),
+            channel_id: Set(channel_id),
+            author_ref: Set(author_ref.clone()),
+            author_hash: Set(author_hash.clone()),
+            pseudonym: Set(pseudonym),
+            content: Set(content.clone()),
+            image: Set(None),
+            status: Set(ConfessionStatus::Pending.into()),
+            vetting_message_id: Set(None),
+            posted_message_id: Set(None),
+            deny_reason: Set(None),
+            claimed_by: Set(None),
+            claimed_until: Set(None),
+            edited_content: Set(None),
+            edited_by: Set(None),
+            content_warning: Set(None),
+            fingerprint: Set(fingerprint),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        let add_result = confessions::Entity::insert(this_confession).exec(db).await;
+        match add_result {
+            Ok(r) => {
+                return match get_confession(db, r.last_insert_id).await? {
+                    Some(model) => Ok(model),
+                    None => Err(anyhow!(
+                        "Confession was not found after adding it to database"
+                    )),
+                }
+            }
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => continue,
+            Err(e) => return Err(anyhow!("Error adding confession to database: {:?}", e)),
+        }
+    }
+    Err(anyhow!(
+        "Error adding confession to database: no free confession number, try again"
+    ))
+}
+
+// Claims are left out so a stale model can't overwrite a claim taken in the meantime.
+pub async fn set_confession(
+    db: &DatabaseConnection,
+    confession: confessions::Model,
+) -> Result<confessions::Model> {
+    let this_confession = confessions::ActiveModel {
+        id: Set(confession.id),
+        guild_id: Set(confession.guild_id),
+        number: Set(confession.number),
+        channel_id: Set(confession.channel_id),
+        author_ref: Set(confession.author_ref),
+        author_hash: Set(confession.author_hash),
+        pseudonym: Set(confession.pseudonym),
+        content: Set(confession.content),
+        image: Set(confession.image),
+        status: Set(confession.status),
+        vetting_message_id: Set(confession.vetting_message_id),
+        posted_message_id: Set(confession.posted_message_id),
+        deny_reason: Set(confession.deny_reason),
+        edited_content: Set(confession.edited_content),
+        edited_by: Set(confession.edited_by),
+        content_warning: Set(confession.content_warning),
+        subject: Set(confession.subject),
+        fingerprint: Set(confession.fingerprint),
+        created_at: Set(confession.created_at),
+        updated_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    let set_result = confessions::Entity::update(this_confession).exec(db).await;
+    match set_result {
+        Ok(r) => Ok(r),
+        Err(e) => Err(anyhow!("Error setting confession in database: {:?}", e)),
+    }
+}
+
+// Only matches pending confessions that are unclaimed, claimed by this moderator or whose claim has run out.
+fn claimable_by(
+    confession_id: i32,
+    moderator_id: u64,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Condition {
+    Condition::all()
+        .add(confessions::Column::Id.eq(confession_id))
+        .add(confessions::Column::Status.eq(ConfessionStatus::Pending))
+        .add(
+            Condition::any()
+                .add(confessions::Column::ClaimedBy.is_null())
+                .add(confessions::Column::ClaimedBy.eq(moderator_id))
+                .add(confessions::Column::ClaimedUntil.lt(now)),
+        )
+}
+
+// Returns false if another moderator holds the claim or the confession is no longer pending.
+pub async fn claim_confession(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    moderator_id: u64,
+    until: chrono::DateTime<chrono::Utc>,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let claim_result = confessions::Entity::update_many()
+        .col_expr(confessions::Column::ClaimedBy, Expr::value(moderator_id))
+        .col_expr(confessions::Column::ClaimedUntil, Expr::value(until))
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(claimable_by(confession_id, moderator_id, now))
+        .exec(db)
+        .await;
+    match claim_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error claiming confession in database: {:?}", e)),
+    }
+}
+
+// Moves a pending confession to `status` in a single statement so only one moderator can act on it.
+pub async fn resolve_confession(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    moderator_id: u64,
+    status: ConfessionStatus,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let resolve_result = confessions::Entity::update_many()
+        .col_expr(confessions::Column::Status, Expr::value(status))
+        .col_expr(confessions::Column::ClaimedBy, Expr::value(moderator_id))
+        .col_expr(confessions::Column::ClaimedUntil, Expr::value(now))
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(claimable_by(confession_id, moderator_id, now))
+        .exec(db)
+        .await;
+    match resolve_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error resolving confession in database: {:?}", e)),
+    }
+}
+
+// Confessions that skip vetting are approved in a single statement, the same way a moderator would.
+pub async fn approve_unvetted_confession(
+    db: &DatabaseConnection,
+    confession_id: i32,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let approve_result = confessions::Entity::update_many()
+        .col_expr(
+            confessions::Column::Status,
+            Expr::value(ConfessionStatus::Approved),
+        )
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(confessions::Column::Id.eq(confession_id))
+        .filter(confessions::Column::Status.eq(ConfessionStatus::Pending))
+        .exec(db)
+        .await;
+    match approve_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error approving confession in database: {:?}", e)),
+    }
+}
+
+// The original content is kept alongside the edit for auditing.
+pub async fn edit_confession(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    moderator_id: u64,
+    content: String,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let edit_result = confessions::Entity::update_many()
+        .col_expr(confessions::Column::EditedContent, Expr::value(content))
+        .col_expr(confessions::Column::EditedBy, Expr::value(moderator_id))
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(claimable_by(confession_id, moderator_id, now))
+        .exec(db)
+        .await;
+    match edit_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error editing confession in database: {:?}", e)),
+    }
+}
+
+pub async fn set_content_warning(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    moderator_id: u64,
+    content_warning: Option<String>,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let set_result = confessions::Entity::update_many()
+        .col_expr(
+            confessions::Column::ContentWarning,
+            Expr::value(content_warning),
+        )
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(claimable_by(confession_id, moderator_id, now))
+        .exec(db)
+        .await;
+    match set_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error setting confession in database: {:?}", e)),
+    }
+}
+
+// Approved questions wait here until the host answers or skips them.
+fn unanswered(confession_id: i32) -> Condition {
+    Condition::all()
+        .add(confessions::Column::Id.eq(confession_id))
+        .add(confessions::Column::Status.eq(ConfessionStatus::Approved))
+        .add(confessions::Column::Answer.is_null())
+        .add(confessions::Column::PostedMessageId.is_null())
+}
+
+pub async fn get_unanswered_questions(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_ids: Vec<u64>,
+) -> Result<Vec<confessions::Model>> {
+    let found_questions = confessions::Entity::find()
+        .filter(confessions::Column::GuildId.eq(guild_id))
+        .filter(confessions::Column::ChannelId.is_in(channel_ids))
+        .filter(confessions::Column::Status.eq(ConfessionStatus::Approved))
+        .filter(confessions::Column::Answer.is_null())
+        .filter(confessions::Column::PostedMessageId.is_null())
+        .order_by_asc(confessions::Column::Number)
+        .all(db)
+        .await;
+    match found_questions {
+        Ok(questions) => Ok(questions),
+        Err(e) => Err(anyhow!("Error getting questions from database: {:?}", e)),
+    }
+}
+
+// Returns false if the question was already answered or skipped.
+pub async fn answer_question(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    host_id: u64,
+    answer: String,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let answer_result = confessions::Entity::update_many()
+        .col_expr(confessions::Column::Answer, Expr::value(answer))
+        .col_expr(confessions::Column::AnsweredBy, Expr::value(host_id))
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(unanswered(confession_id))
+        .exec(db)
+        .await;
+    match answer_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error answering question in database: {:?}", e)),
+    }
+}
+
+// Undoes an answer that could not be posted so the host can try again.
+pub async fn clear_answer(db: &DatabaseConnection, confession_id: i32) -> Result<()> {
+    let now = chrono::Utc::now();
+    let clear_result = confessions::Entity::update_many()
+        .col_expr(confessions::Column::Answer, Expr::value(None::<String>))
+        .col_expr(confessions::Column::AnsweredBy, Expr::value(None::<u64>))
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(confessions::Column::Id.eq(confession_id))
+        .filter(confessions::Column::PostedMessageId.is_null())
+        .exec(db)
+        .await;
+    match clear_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error clearing answer in database: {:?}", e)),
+    }
+}
+
+// Returns false if the question was already answered or skipped.
+pub async fn skip_question(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    host_id: u64,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let skip_result = confessions::Entity::update_many()
+        .col_expr(
+            confessions::Column::Status,
+            Expr::value(ConfessionStatus::Skipped),
+        )
+        .col_expr(confessions::Column::AnsweredBy, Expr::value(host_id))
+        .col_expr(confessions::Column::UpdatedAt, Expr::value(now))
+        .filter(unanswered(confession_id))
+        .exec(db)
+        .await;
+    match skip_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error skipping question in database: {:?}", e)),
+    }
+}
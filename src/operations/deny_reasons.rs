@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, InsertResult, QueryFilter, Set};
+
+use crate::entity::guild_deny_reasons;
+
+pub async fn get_guild_deny_reasons_raw(
+    db: &DatabaseConnection,
+    guild_id: u64,
+) -> Result<Vec<guild_deny_reasons::Model>> {
+    match guild_deny_reasons::Entity::find()
+        .filter(guild_deny_reasons::Column::GuildId.eq(guild_id))
+        .all(db)
+        .await
+    {
+        Ok(reasons) => Ok(reasons),
+        Err(why) => Err(anyhow!(
+            "Error getting deny reasons from database: {:?}",
+            why
+        )),
+    }
+}
+
+pub async fn get_guild_deny_reasons(db: &DatabaseConnection, guild_id: u64) -> Result<Vec<String>> {
+    match get_guild_deny_reasons_raw(db, guild_id).await {
+        Ok(reasons) => Ok(reasons.into_iter().map(|r| r.reason).collect()),
+        Err(why) => Err(why),
+    }
+}
+
+pub async fn get_deny_reason(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    id: i32,
+) -> Result<Option<guild_deny_reasons::Model>> {
+    let found_reason = guild_deny_reasons::Entity::find_by_id(id)
+        .filter(guild_deny_reasons::Column::GuildId.eq(guild_id))
+        .one(db)
+        .await;
+    match found_reason {
+        Ok(r) => Ok(r),
+        Err(e) => Err(anyhow!("Error getting deny reason from database: {:?}", e)),
+    }
+}
+
+pub async fn guild_has_deny_reason(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    reason: String,
+) -> Result<Option<guild_deny_reasons::Model>> {
+    let found_reason = guild_deny_reasons::Entity::find()
+        .filter(guild_deny_reasons::Column::GuildId.eq(guild_id))
+        .filter(guild_deny_reasons::Column::Reason.eq(reason))
+        .one(db)
+        .await;
+    match found_reason {
+        Ok(r) => Ok(r),
+        Err(e) => Err(anyhow!("Error getting deny reason from database: {:?}", e)),
+    }
+}
+
+pub async fn add_guild_deny_reason(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    reason: String,
+) -> Result<InsertResult<guild_deny_reasons::ActiveModel>> {
+    if let Ok(Some(model)) = guild_has_deny_reason(db, guild_id, reason.clone()).await {
+        return Err(anyhow!(
+            "Deny reason already exists in database: {:?}",
+            model
+        ));
+    }
+    let this_reason = guild_deny_reasons::ActiveModel {
+        guild_id: Set(guild_id),
+        reason: Set(reason),
+        ..Default::default()
+    };
+    match guild_deny_reasons::Entity::insert(this_reason)
+        .exec(db)
+        .await
+    {
+        Ok(r) => Ok(r),
+        Err(e) => Err(anyhow!("Error adding deny reason to database: {:?}", e)),
+    }
+}
+
+pub async fn remove_guild_deny_reason(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    reason: String,
+) -> Result<()> {
+    if let Ok(Some(model)) = guild_has_deny_reason(db, guild_id, reason).await {
+        match guild_deny_reasons::Entity::delete_by_id(model.id)
+            .exec(db)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(anyhow!("Error removing deny reason from database: {:?}", e)),
+        }
+    } else {
+        Err(anyhow!("Deny reason not found in database"))
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QuerySelect, Set,
+};
+
+use crate::entity::{direct_confessions, direct_messages};
+
+use super::confessions::ConfessionStatus;
+
+pub async fn get_direct_confession(
+    db: &DatabaseConnection,
+    id: i32,
+) -> Result<Option<direct_confessions::Model>> {
+    match direct_confessions::Entity::find_by_id(id).one(db).await {
+        Ok(d) => Ok(d),
+        Err(e) => Err(anyhow!(
+            "Error getting direct confession from database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn add_direct_confession(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_ref: String,
+    author_hash: String,
+    recipient_id: u64,
+    content: String,
+) -> Result<direct_confessions::Model> {
+    let this_direct_confession = direct_confessions::ActiveModel {
+        guild_id: Set(guild_id),
+        author_ref: Set(author_ref),
+        author_hash: Set(author_hash),
+        recipient_id: Set(recipient_id),
+        content: Set(content),
+        status: Set(ConfessionStatus::Pending.into()),
+        vetting_message_id: Set(None),
+        blocked: Set(0),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    let add_result = direct_confessions::Entity::insert(this_direct_confession)
+        .exec(db)
+        .await;
+    match add_result {
+        Ok(r) => match get_direct_confession(db, r.last_insert_id).await? {
+            Some(model) => Ok(model),
+            None => Err(anyhow!(
+                "Direct confession was not found after adding it to database"
+            )),
+        },
+        Err(e) => Err(anyhow!(
+            "Error adding direct confession to database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn set_direct_vetting_message(
+    db: &DatabaseConnection,
+    id: i32,
+    vetting_message_id: u64,
+) -> Result<()> {
+    let set_result = direct_confessions::Entity::update_many()
+        .col_expr(
+            direct_confessions::Column::VettingMessageId,
+            Expr::value(vetting_message_id),
+        )
+        .filter(direct_confessions::Column::Id.eq(id))
+        .exec(db)
+        .await;
+    match set_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!(
+            "Error setting direct confession in database: {:?}",
+            e
+        )),
+    }
+}
+
+// Moves a pending direct confession to `status`, returning false if a moderator already did.
+pub async fn resolve_direct_confession(
+    db: &DatabaseConnection,
+    id: i32,
+    status: ConfessionStatus,
+) -> Result<bool> {
+    let resolve_result = direct_confessions::Entity::update_many()
+        .col_expr(direct_confessions::Column::Status, Expr::value(status))
+        .filter(direct_confessions::Column::Id.eq(id))
+        .filter(direct_confessions::Column::Status.eq(ConfessionStatus::Pending))
+        .exec(db)
+        .await;
+    match resolve_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!(
+            "Error resolving direct confession in database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn block_direct_sender(db: &DatabaseConnection, id: i32) -> Result<()> {
+    let block_result = direct_confessions::Entity::update_many()
+        .col_expr(direct_confessions::Column::Blocked, Expr::value(1))
+        .filter(direct_confessions::Column::Id.eq(id))
+        .exec(db)
+        .await;
+    match block_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!(
+            "Error blocking direct confession sender in database: {:?}",
+            e
+        )),
+    }
+}
+
+// A block on any conversation stops the same sender from reaching that member again.
+pub async fn is_direct_sender_blocked(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+    recipient_id: u64,
+) -> Result<bool> {
+    let blocked_count = direct_confessions::Entity::find()
+        .filter(direct_confessions::Column::GuildId.eq(guild_id))
+        .filter(direct_confessions::Column::AuthorHash.eq(author_hash))
+        .filter(direct_confessions::Column::RecipientId.eq(recipient_id))
+        .filter(direct_confessions::Column::Blocked.eq(1))
+        .count(db)
+        .await;
+    match blocked_count {
+        Ok(count) => Ok(count > 0),
+        Err(e) => Err(anyhow!(
+            "Error getting direct confession blocks from database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn count_author_pending_direct(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+) -> Result<u64> {
+    let pending_count = direct_confessions::Entity::find()
+        .filter(direct_confessions::Column::GuildId.eq(guild_id))
+        .filter(direct_confessions::Column::AuthorHash.eq(author_hash))
+        .filter(direct_confessions::Column::Status.eq(ConfessionStatus::Pending))
+        .count(db)
+        .await;
+    match pending_count {
+        Ok(count) => Ok(count),
+        Err(e) => Err(anyhow!(
+            "Error counting direct confessions in database: {:?}",
+            e
+        )),
+    }
+}
+
+// Records a message the sender relayed after their direct confession was approved.
+pub async fn add_direct_message(
+    db: &DatabaseConnection,
+    direct_confession: &direct_confessions::Model,
+) -> Result<()> {
+    let this_message = direct_messages::ActiveModel {
+        direct_confession_id: Set(direct_confession.id),
+        guild_id: Set(direct_confession.guild_id),
+        author_hash: Set(direct_confession.author_hash.clone()),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    match direct_messages::Entity::insert(this_message).exec(db).await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error adding direct message to database: {:?}", e)),
+    }
+}
+
+// When the sender's direct confessions and relayed messages since `since` were sent, oldest first.
+pub async fn get_author_direct_times_since(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_hash: &str,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<chrono::DateTime<chrono::Utc>>> {
+    let confessed = direct_confessions::Entity::find()
+        .select_only()
+        .column(direct_confessions::Column::CreatedAt)
+        .filter(direct_confessions::Column::GuildId.eq(guild_id))
+        .filter(direct_confessions::Column::AuthorHash.eq(author_hash))
+        .filter(direct_confessions::Column::CreatedAt.gte(since))
+        .into_tuple::<chrono::DateTime<chrono::Utc>>()
+        .all(db)
+        .await;
+    let relayed = direct_messages::Entity::find()
+        .select_only()
+        .column(direct_messages::Column::CreatedAt)
+        .filter(direct_messages::Column::GuildId.eq(guild_id))
+        .filter(direct_messages::Column::AuthorHash.eq(author_hash))
+        .filter(direct_messages::Column::CreatedAt.gte(since))
+        .into_tuple::<chrono::DateTime<chrono::Utc>>()
+        .all(db)
+        .await;
+    match (confessed, relayed) {
+        (Ok(mut times), Ok(relayed)) => {
+            times.extend(relayed);
+            times.sort();
+            Ok(times)
+        }
+        (Err(e), _) | (_, Err(e)) => Err(anyhow!(
+            "Error getting direct messages from database: {:?}",
+            e
+        )),
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::guild_filter_rules;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum FilterKind {
+    #[name = "banned word"]
+    Word,
+    #[name = "regex"]
+    Regex,
+    #[name = "invite links"]
+    Invite,
+    #[name = "links"]
+    Url,
+    #[name = "mass mentions"]
+    MassMention,
+    #[name = "max lines"]
+    MaxLines,
+    #[name = "zalgo"]
+    Zalgo,
+}
+
+impl From<FilterKind> for i32 {
+    fn from(kind: FilterKind) -> Self {
+        match kind {
+            FilterKind::Word => 0,
+            FilterKind::Regex => 1,
+            FilterKind::Invite => 2,
+            FilterKind::Url => 3,
+            FilterKind::MassMention => 4,
+            FilterKind::MaxLines => 5,
+            FilterKind::Zalgo => 6,
+        }
+    }
+}
+
+impl From<i32> for FilterKind {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => FilterKind::Word,
+            1 => FilterKind::Regex,
+            2 => FilterKind::Invite,
+            3 => FilterKind::Url,
+            4 => FilterKind::MassMention,
+            5 => FilterKind::MaxLines,
+            6 => FilterKind::Zalgo,
+            _ => FilterKind::Word,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum FilterAction {
+    #[name = "reject"]
+    Reject,
+    #[name = "flag"]
+    Flag,
+    #[name = "redact"]
+    Redact,
+}
+
+impl From<FilterAction> for i32 {
+    fn from(action: FilterAction) -> Self {
+        match action {
+            FilterAction::Reject => 0,
+            FilterAction::Flag => 1,
+            FilterAction::Redact => 2,
+        }
+    }
+}
+
+impl From<i32> for FilterAction {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => FilterAction::Reject,
+            1 => FilterAction::Flag,
+            2 => FilterAction::Redact,
+            _ => FilterAction::Flag,
+        }
+    }
+}
+
+pub async fn get_guild_filters(
+    db: &DatabaseConnection,
+    guild_id: u64,
+) -> Result<Vec<guild_filter_rules::Model>> {
+    let found_filters = guild_filter_rules::Entity::find()
+        .filter(guild_filter_rules::Column::GuildId.eq(guild_id))
+        .all(db)
+        .await;
+    match found_filters {
+        Ok(filters) => Ok(filters),
+        Err(e) => Err(anyhow!("Error getting filters from database: {:?}", e)),
+    }
+}
+
+pub async fn add_guild_filter(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    kind: FilterKind,
+    pattern: Option<String>,
+    action: FilterAction,
+) -> Result<i32> {
+    let this_filter = guild_filter_rules::ActiveModel {
+        guild_id: Set(guild_id),
+        kind: Set(kind.into()),
+        pattern: Set(pattern),
+        action: Set(action.into()),
+        ..Default::default()
+    };
+    let add_result = guild_filter_rules::Entity::insert(this_filter)
+        .exec(db)
+        .await;
+    match add_result {
+        Ok(r) => Ok(r.last_insert_id),
+        Err(e) => Err(anyhow!("Error adding filter to database: {:?}", e)),
+    }
+}
+
+pub async fn remove_guild_filter(db: &DatabaseConnection, guild_id: u64, id: i32) -> Result<()> {
+    let remove_result = guild_filter_rules::Entity::delete_many()
+        .filter(guild_filter_rules::Column::GuildId.eq(guild_id))
+        .filter(guild_filter_rules::Column::Id.eq(id))
+        .exec(db)
+        .await;
+    match remove_result {
+        Ok(r) if r.rows_affected > 0 => Ok(()),
+        Ok(_) => Err(anyhow!("There is no filter with ID {}.", id)),
+        Err(e) => Err(anyhow!("Error removing filter from database: {:?}", e)),
+    }
+}
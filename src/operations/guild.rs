@@ -1,9 +1,41 @@
 use anyhow::{anyhow, Result};
-use sea_orm::{DatabaseConnection, EntityTrait, InsertResult, Set, sea_query::OnConflict};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, InsertResult, QueryFilter, Set, sea_query::OnConflict};
+use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use crate::entity::guild;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum DuplicateAction {
+    #[name = "ignore"]
+    Ignore,
+    #[name = "flag"]
+    Flag,
+    #[name = "reject"]
+    Reject,
+}
+
+impl From<DuplicateAction> for i32 {
+    fn from(action: DuplicateAction) -> Self {
+        match action {
+            DuplicateAction::Ignore => 0,
+            DuplicateAction::Flag => 1,
+            DuplicateAction::Reject => 2,
+        }
+    }
+}
+
+impl From<i32> for DuplicateAction {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => DuplicateAction::Ignore,
+            1 => DuplicateAction::Flag,
+            2 => DuplicateAction::Reject,
+            _ => DuplicateAction::Flag,
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub async fn get_guilds(db: &DatabaseConnection) -> Option<Vec<guild::Model>> {
     guild::Entity::find().all(db).await.ok()
@@ -23,6 +55,21 @@ pub async fn add_or_nothing_guild(
     let this_guild = guild::ActiveModel {
         id: Set(guild_id),
         admin_role: Set(None),
+        vet_replies: Set(0),
+        required_approvals: Set(1),
+        required_denials: Set(1),
+        pending_expiry_hours: Set(0),
+        trusted_role: Set(None),
+        trusted_after: Set(0),
+        duplicate_action: Set(DuplicateAction::Flag.into()),
+        max_attachments: Set(1),
+        max_attachment_mb: Set(8),
+        allow_video: Set(0),
+        allow_audio: Set(0),
+        allowed_file_types: Set(None),
+        post_delay_minutes: Set(0),
+        post_jitter_minutes: Set(0),
+        post_batch_minutes: Set(0),
     };
     let add_result = guild::Entity::insert(this_guild.clone())
         .on_conflict(
@@ -48,6 +95,21 @@ pub async fn set_guild(
     let this_guild = guild::ActiveModel {
         id: Set(guild.id),
         admin_role: Set(guild.admin_role),
+        vet_replies: Set(guild.vet_replies),
+        required_approvals: Set(guild.required_approvals),
+        required_denials: Set(guild.required_denials),
+        pending_expiry_hours: Set(guild.pending_expiry_hours),
+        trusted_role: Set(guild.trusted_role),
+        trusted_after: Set(guild.trusted_after),
+        duplicate_action: Set(guild.duplicate_action),
+        max_attachments: Set(guild.max_attachments),
+        max_attachment_mb: Set(guild.max_attachment_mb),
+        allow_video: Set(guild.allow_video),
+        allow_audio: Set(guild.allow_audio),
+        allowed_file_types: Set(guild.allowed_file_types),
+        post_delay_minutes: Set(guild.post_delay_minutes),
+        post_jitter_minutes: Set(guild.post_jitter_minutes),
+        post_batch_minutes: Set(guild.post_batch_minutes),
     };
     let add_result = guild::Entity::update(this_guild.clone())
         .exec(db)
@@ -72,3 +134,46 @@ pub async fn get_guild_mod_role(db: &DatabaseConnection, guild_id: u64) -> Resul
         Err(e) => Err(anyhow!("Error getting guild from database: {:?}", e)),
     }
 }
+
+pub async fn get_guild_vet_replies(db: &DatabaseConnection, guild_id: u64) -> Result<bool> {
+    match guild::Entity::find_by_id(guild_id).one(db).await {
+        Ok(g) => Ok(g.map(|guild| guild.vet_replies == 1).unwrap_or(false)),
+        Err(e) => Err(anyhow!("Error getting guild from database: {:?}", e)),
+    }
+}
+
+// (approvals, denials) needed before a confession is posted or denied.
+pub async fn get_guild_vetting_quorum(
+    db: &DatabaseConnection,
+    guild_id: u64,
+) -> Result<(u32, u32)> {
+    match guild::Entity::find_by_id(guild_id).one(db).await {
+        Ok(g) => Ok(g
+            .map(|guild| (guild.required_approvals, guild.required_denials))
+            .unwrap_or((1, 1))),
+        Err(e) => Err(anyhow!("Error getting guild from database: {:?}", e)),
+    }
+}
+
+pub async fn get_guilds_with_pending_expiry(db: &DatabaseConnection) -> Result<Vec<guild::Model>> {
+    let found_guilds = guild::Entity::find()
+        .filter(guild::Column::PendingExpiryHours.gt(0))
+        .all(db)
+        .await;
+    match found_guilds {
+        Ok(guilds) => Ok(guilds),
+        Err(e) => Err(anyhow!("Error getting guilds from database: {:?}", e)),
+    }
+}
+
+pub async fn get_guild_duplicate_action(
+    db: &DatabaseConnection,
+    guild_id: u64,
+) -> Result<DuplicateAction> {
+    match guild::Entity::find_by_id(guild_id).one(db).await {
+        Ok(g) => Ok(g
+            .map(|guild| DuplicateAction::from(guild.duplicate_action))
+            .unwrap_or(DuplicateAction::Flag)),
+        Err(e) => Err(anyhow!("Error getting guild from database: {:?}", e)),
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::member_settings;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum SubjectNotifications {
+    #[name = "mention in the post"]
+    Mention,
+    #[name = "direct message"]
+    Direct,
+    #[name = "off"]
+    Off,
+}
+
+impl From<SubjectNotifications> for i32 {
+    fn from(notifications: SubjectNotifications) -> Self {
+        match notifications {
+            SubjectNotifications::Mention => 0,
+            SubjectNotifications::Direct => 1,
+            SubjectNotifications::Off => 2,
+        }
+    }
+}
+
+impl From<i32> for SubjectNotifications {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => SubjectNotifications::Mention,
+            1 => SubjectNotifications::Direct,
+            2 => SubjectNotifications::Off,
+            _ => SubjectNotifications::Mention,
+        }
+    }
+}
+
+impl From<SubjectNotifications> for sea_orm::Value {
+    fn from(notifications: SubjectNotifications) -> Self {
+        let notifications: i32 = notifications.into();
+        sea_orm::Value::Int(Some(notifications))
+    }
+}
+
+pub async fn get_member_settings(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    user_id: u64,
+) -> Result<Option<member_settings::Model>> {
+    let found_settings = member_settings::Entity::find()
+        .filter(member_settings::Column::GuildId.eq(guild_id))
+        .filter(member_settings::Column::UserId.eq(user_id))
+        .one(db)
+        .await;
+    match found_settings {
+        Ok(settings) => Ok(settings),
+        Err(e) => Err(anyhow!(
+            "Error getting member settings from database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn get_members_settings(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    user_ids: Vec<u64>,
+) -> Result<Vec<member_settings::Model>> {
+    let found_settings = member_settings::Entity::find()
+        .filter(member_settings::Column::GuildId.eq(guild_id))
+        .filter(member_settings::Column::UserId.is_in(user_ids))
+        .all(db)
+        .await;
+    match found_settings {
+        Ok(settings) => Ok(settings),
+        Err(e) => Err(anyhow!(
+            "Error getting member settings from database: {:?}",
+            e
+        )),
+    }
+}
+
+// Members without a row yet get one, leaving the columns not being set at their defaults.
+async fn save_member_settings(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    user_id: u64,
+    set: impl FnOnce(&mut member_settings::ActiveModel),
+) -> Result<()> {
+    let mut this_settings = match get_member_settings(db, guild_id, user_id).await? {
+        Some(settings) => settings.into(),
+        None => member_settings::ActiveModel {
+            guild_id: Set(guild_id),
+            user_id: Set(user_id),
+            ..Default::default()
+        },
+    };
+    set(&mut this_settings);
+    match this_settings.save(db).await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!(
+            "Error setting member settings in database: {:?}",
+            e
+        )),
+    }
+}
+
+// Members have to opt in before anyone can confess to them directly.
+pub async fn get_accepts_direct(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    user_id: u64,
+) -> Result<bool> {
+    Ok(get_member_settings(db, guild_id, user_id)
+        .await?
+        .map(|settings| settings.accept_direct == 1)
+        .unwrap_or(false))
+}
+
+pub async fn set_accepts_direct(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    user_id: u64,
+    accept_direct: bool,
+) -> Result<()> {
+    save_member_settings(db, guild_id, user_id, |settings| {
+        settings.accept_direct = Set(accept_direct as i8)
+    })
+    .await
+}
+
+pub async fn set_subject_notifications(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    user_id: u64,
+    notifications: SubjectNotifications,
+) -> Result<()> {
+    save_member_settings(db, guild_id, user_id, |settings| {
+        settings.subject_notifications = Set(notifications.into())
+    })
+    .await
+}
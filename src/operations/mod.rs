@@ -1,4 +1,18 @@
+pub mod attachments;
+pub mod blocks;
 pub mod channels;
+pub mod confessions;
+pub mod deny_reasons;
+pub mod direct_confessions;
+pub mod filters;
 pub mod guild;
 pub mod guild_confessions;
-pub mod subjects;
\ No newline at end of file
+pub mod member_settings;
+pub mod modmail;
+pub mod polls;
+pub mod pseudonyms;
+pub mod rate_limits;
+pub mod replies;
+pub mod scheduled_posts;
+pub mod subjects;
+pub mod votes;
\ No newline at end of file
@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+
+use crate::entity::modmail_threads;
+
+pub async fn add_modmail_thread(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    author_ref: String,
+    author_hash: String,
+    pseudonym: u32,
+    thread_id: u64,
+) -> Result<modmail_threads::Model> {
+    let this_thread = modmail_threads::ActiveModel {
+        guild_id: Set(guild_id),
+        author_ref: Set(author_ref),
+        author_hash: Set(author_hash),
+        pseudonym: Set(pseudonym),
+        thread_id: Set(thread_id),
+        closed: Set(0),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    let add_result = modmail_threads::Entity::insert(this_thread).exec(db).await;
+    match add_result {
+        Ok(r) => match get_modmail_thread(db, r.last_insert_id).await? {
+            Some(model) => Ok(model),
+            None => Err(anyhow!(
+                "Modmail thread was not found after adding it to database"
+            )),
+        },
+        Err(e) => Err(anyhow!("Error adding modmail thread to database: {:?}", e)),
+    }
+}
+
+pub async fn get_modmail_thread(
+    db: &DatabaseConnection,
+    id: i32,
+) -> Result<Option<modmail_threads::Model>> {
+    match modmail_threads::Entity::find_by_id(id).one(db).await {
+        Ok(thread) => Ok(thread),
+        Err(e) => Err(anyhow!(
+            "Error getting modmail thread from database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn get_modmail_thread_by_channel(
+    db: &DatabaseConnection,
+    thread_id: u64,
+) -> Result<Option<modmail_threads::Model>> {
+    let found_thread = modmail_threads::Entity::find()
+        .filter(modmail_threads::Column::ThreadId.eq(thread_id))
+        .one(db)
+        .await;
+    match found_thread {
+        Ok(thread) => Ok(thread),
+        Err(e) => Err(anyhow!(
+            "Error getting modmail thread from database: {:?}",
+            e
+        )),
+    }
+}
+
+// Returns false if the thread was already closed.
+pub async fn close_modmail_thread(db: &DatabaseConnection, id: i32) -> Result<bool> {
+    let close_result = modmail_threads::Entity::update_many()
+        .col_expr(modmail_threads::Column::Closed, Expr::value(1))
+        .filter(modmail_threads::Column::Id.eq(id))
+        .filter(modmail_threads::Column::Closed.eq(0))
+        .exec(db)
+        .await;
+    match close_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error closing modmail thread in database: {:?}", e)),
+    }
+}
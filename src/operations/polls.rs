@@ -0,0 +1,157 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+
+use crate::entity::{confession_polls, poll_votes};
+
+// Options are stored as a JSON list so their order is kept.
+pub fn get_poll_options(poll: &confession_polls::Model) -> Vec<String> {
+    serde_json::from_str(&poll.options).unwrap_or_default()
+}
+
+pub async fn add_poll(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    options: Vec<String>,
+    duration_hours: u32,
+) -> Result<()> {
+    let options = match serde_json::to_string(&options) {
+        Ok(options) => options,
+        Err(e) => return Err(anyhow!("Error saving poll options: {:?}", e)),
+    };
+    let this_poll = confession_polls::ActiveModel {
+        confession_id: Set(confession_id),
+        options: Set(options),
+        duration_hours: Set(duration_hours),
+        closes_at: Set(None),
+        closed: Set(0),
+        ..Default::default()
+    };
+    let add_result = confession_polls::Entity::insert(this_poll).exec(db).await;
+    match add_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error adding poll to database: {:?}", e)),
+    }
+}
+
+pub async fn get_poll(
+    db: &DatabaseConnection,
+    poll_id: i32,
+) -> Result<Option<confession_polls::Model>> {
+    match confession_polls::Entity::find_by_id(poll_id).one(db).await {
+        Ok(poll) => Ok(poll),
+        Err(e) => Err(anyhow!("Error getting poll from database: {:?}", e)),
+    }
+}
+
+pub async fn get_confession_poll(
+    db: &DatabaseConnection,
+    confession_id: i32,
+) -> Result<Option<confession_polls::Model>> {
+    let found_poll = confession_polls::Entity::find()
+        .filter(confession_polls::Column::ConfessionId.eq(confession_id))
+        .one(db)
+        .await;
+    match found_poll {
+        Ok(poll) => Ok(poll),
+        Err(e) => Err(anyhow!("Error getting poll from database: {:?}", e)),
+    }
+}
+
+// The deadline is only known once the poll is posted.
+pub async fn open_poll(
+    db: &DatabaseConnection,
+    poll_id: i32,
+    closes_at: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    let open_result = confession_polls::Entity::update_many()
+        .col_expr(confession_polls::Column::ClosesAt, Expr::value(closes_at))
+        .filter(confession_polls::Column::Id.eq(poll_id))
+        .exec(db)
+        .await;
+    match open_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error setting poll in database: {:?}", e)),
+    }
+}
+
+pub async fn get_expired_polls(
+    db: &DatabaseConnection,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<confession_polls::Model>> {
+    let found_polls = confession_polls::Entity::find()
+        .filter(confession_polls::Column::Closed.eq(0))
+        .filter(confession_polls::Column::ClosesAt.lte(now))
+        .all(db)
+        .await;
+    match found_polls {
+        Ok(polls) => Ok(polls),
+        Err(e) => Err(anyhow!("Error getting polls from database: {:?}", e)),
+    }
+}
+
+// False if the poll was already closed.
+pub async fn close_poll(db: &DatabaseConnection, poll_id: i32) -> Result<bool> {
+    let close_result = confession_polls::Entity::update_many()
+        .col_expr(confession_polls::Column::Closed, Expr::value(1))
+        .filter(confession_polls::Column::Id.eq(poll_id))
+        .filter(confession_polls::Column::Closed.eq(0))
+        .exec(db)
+        .await;
+    match close_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error setting poll in database: {:?}", e)),
+    }
+}
+
+pub async fn get_poll_votes(
+    db: &DatabaseConnection,
+    poll_id: i32,
+) -> Result<Vec<poll_votes::Model>> {
+    let found_votes = poll_votes::Entity::find()
+        .filter(poll_votes::Column::PollId.eq(poll_id))
+        .all(db)
+        .await;
+    match found_votes {
+        Ok(votes) => Ok(votes),
+        Err(e) => Err(anyhow!("Error getting poll votes from database: {:?}", e)),
+    }
+}
+
+// Voting again moves the voter's one vote to the new option.
+pub async fn set_poll_vote(
+    db: &DatabaseConnection,
+    poll_id: i32,
+    voter_hash: String,
+    option_index: u32,
+) -> Result<()> {
+    let existing = poll_votes::Entity::find()
+        .filter(poll_votes::Column::PollId.eq(poll_id))
+        .filter(poll_votes::Column::VoterHash.eq(voter_hash.clone()))
+        .one(db)
+        .await;
+    let result = match existing {
+        Ok(Some(vote)) => poll_votes::Entity::update(poll_votes::ActiveModel {
+            id: Set(vote.id),
+            poll_id: Set(poll_id),
+            voter_hash: Set(voter_hash),
+            option_index: Set(option_index),
+        })
+        .exec(db)
+        .await
+        .map(|_| ()),
+        Ok(None) => poll_votes::Entity::insert(poll_votes::ActiveModel {
+            poll_id: Set(poll_id),
+            voter_hash: Set(voter_hash),
+            option_index: Set(option_index),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .map(|_| ()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error setting poll vote in database: {:?}", e)),
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+
+use crate::entity::guild_pseudonyms;
+
+pub async fn get_author_pseudonym(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    epoch: u64,
+    author_hash: String,
+) -> Result<Option<u32>> {
+    let found_pseudonym = guild_pseudonyms::Entity::find()
+        .filter(guild_pseudonyms::Column::GuildId.eq(guild_id))
+        .filter(guild_pseudonyms::Column::Epoch.eq(epoch))
+        .filter(guild_pseudonyms::Column::AuthorHash.eq(author_hash))
+        .one(db)
+        .await;
+    match found_pseudonym {
+        Ok(p) => Ok(p.map(|p| p.pseudonym)),
+        Err(e) => Err(anyhow!("Error getting pseudonym from database: {:?}", e)),
+    }
+}
+
+pub async fn is_pseudonym_taken(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    epoch: u64,
+    pseudonym: u32,
+) -> Result<bool> {
+    let found_pseudonym = guild_pseudonyms::Entity::find()
+        .filter(guild_pseudonyms::Column::GuildId.eq(guild_id))
+        .filter(guild_pseudonyms::Column::Epoch.eq(epoch))
+        .filter(guild_pseudonyms::Column::Pseudonym.eq(pseudonym))
+        .one(db)
+        .await;
+    match found_pseudonym {
+        Ok(p) => Ok(p.is_some()),
+        Err(e) => Err(anyhow!("Error getting pseudonym from database: {:?}", e)),
+    }
+}
+
+// The unique indexes on the table reject a pseudonym (or author) claimed concurrently.
+pub async fn add_pseudonym(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    epoch: u64,
+    author_hash: String,
+    pseudonym: u32,
+) -> Result<()> {
+    let this_pseudonym = guild_pseudonyms::ActiveModel {
+        guild_id: Set(guild_id),
+        epoch: Set(epoch),
+        author_hash: Set(author_hash),
+        pseudonym: Set(pseudonym),
+        ..Default::default()
+    };
+    match guild_pseudonyms::Entity::insert(this_pseudonym)
+        .exec(db)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error adding pseudonym to database: {:?}", e)),
+    }
+}
+
+pub async fn clear_stale_pseudonyms(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    current_epoch: u64,
+) -> Result<()> {
+    let remove_result = guild_pseudonyms::Entity::delete_many()
+        .filter(guild_pseudonyms::Column::GuildId.eq(guild_id))
+        .filter(guild_pseudonyms::Column::Epoch.ne(current_epoch))
+        .exec(db)
+        .await;
+    match remove_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error removing pseudonyms from database: {:?}", e)),
+    }
+}
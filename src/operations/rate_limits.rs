@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set};
+
+use crate::entity::guild_rate_limits;
+
+fn limit_scope(guild_id: u64, channel_id: Option<u64>) -> Condition {
+    let channel_condition = match channel_id {
+        Some(channel_id) => guild_rate_limits::Column::ChannelId.eq(channel_id),
+        None => guild_rate_limits::Column::ChannelId.is_null(),
+    };
+    Condition::all()
+        .add(guild_rate_limits::Column::GuildId.eq(guild_id))
+        .add(channel_condition)
+}
+
+pub async fn get_guild_rate_limits(
+    db: &DatabaseConnection,
+    guild_id: u64,
+) -> Result<Vec<guild_rate_limits::Model>> {
+    let found_limits = guild_rate_limits::Entity::find()
+        .filter(guild_rate_limits::Column::GuildId.eq(guild_id))
+        .all(db)
+        .await;
+    match found_limits {
+        Ok(limits) => Ok(limits),
+        Err(e) => Err(anyhow!("Error getting rate limits from database: {:?}", e)),
+    }
+}
+
+// The guild-wide limit and the limit for this channel, whichever are set.
+pub async fn get_rate_limits_for_channel(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: u64,
+) -> Result<Vec<guild_rate_limits::Model>> {
+    let found_limits = guild_rate_limits::Entity::find()
+        .filter(
+            Condition::any()
+                .add(limit_scope(guild_id, None))
+                .add(limit_scope(guild_id, Some(channel_id))),
+        )
+        .all(db)
+        .await;
+    match found_limits {
+        Ok(limits) => Ok(limits),
+        Err(e) => Err(anyhow!("Error getting rate limits from database: {:?}", e)),
+    }
+}
+
+pub async fn set_rate_limit(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: Option<u64>,
+    max_confessions: u32,
+    period_minutes: u32,
+    max_pending: u32,
+) -> Result<()> {
+    let existing = guild_rate_limits::Entity::find()
+        .filter(limit_scope(guild_id, channel_id))
+        .one(db)
+        .await;
+    let result = match existing {
+        Ok(Some(limit)) => guild_rate_limits::Entity::update(guild_rate_limits::ActiveModel {
+            id: Set(limit.id),
+            guild_id: Set(guild_id),
+            channel_id: Set(channel_id),
+            max_confessions: Set(max_confessions),
+            period_minutes: Set(period_minutes),
+            max_pending: Set(max_pending),
+        })
+        .exec(db)
+        .await
+        .map(|_| ()),
+        Ok(None) => guild_rate_limits::Entity::insert(guild_rate_limits::ActiveModel {
+            guild_id: Set(guild_id),
+            channel_id: Set(channel_id),
+            max_confessions: Set(max_confessions),
+            period_minutes: Set(period_minutes),
+            max_pending: Set(max_pending),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .map(|_| ()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error setting rate limit in database: {:?}", e)),
+    }
+}
+
+pub async fn remove_rate_limit(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    channel_id: Option<u64>,
+) -> Result<()> {
+    let remove_result = guild_rate_limits::Entity::delete_many()
+        .filter(limit_scope(guild_id, channel_id))
+        .exec(db)
+        .await;
+    match remove_result {
+        Ok(r) if r.rows_affected > 0 => Ok(()),
+        Ok(_) => Err(anyhow!("There is no rate limit set there.")),
+        Err(e) => Err(anyhow!("Error removing rate limit from database: {:?}", e)),
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+
+use crate::entity::{confession_replies, reply_pseudonyms};
+
+use super::confessions::ConfessionStatus;
+
+pub async fn get_reply(
+    db: &DatabaseConnection,
+    id: i32,
+) -> Result<Option<confession_replies::Model>> {
+    match confession_replies::Entity::find_by_id(id).one(db).await {
+        Ok(r) => Ok(r),
+        Err(e) => Err(anyhow!("Error getting reply from database: {:?}", e)),
+    }
+}
+
+pub async fn get_replies_to_confession(
+    db: &DatabaseConnection,
+    confession_id: i32,
+) -> Result<Vec<confession_replies::Model>> {
+    let found_replies = confession_replies::Entity::find()
+        .filter(confession_replies::Column::ConfessionId.eq(confession_id))
+        .all(db)
+        .await;
+    match found_replies {
+        Ok(replies) => Ok(replies),
+        Err(e) => Err(anyhow!("Error getting replies from database: {:?}", e)),
+    }
+}
+
+pub async fn add_reply(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    author_hash: String,
+    guild_author_hash: String,
+    pseudonym: String,
+    content: String,
+) -> Result<confession_replies::Model> {
+    let this_reply = confession_replies::ActiveModel {
+        confession_id: Set(confession_id),
+        author_hash: Set(author_hash),
+        guild_author_hash: Set(Some(guild_author_hash)),
+        pseudonym: Set(pseudonym),
+        content: Set(content),
+        status: Set(ConfessionStatus::Pending.into()),
+        vetting_message_id: Set(None),
+        posted_message_id: Set(None),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    let add_result = confession_replies::Entity::insert(this_reply)
+        .exec(db)
+        .await;
+    match add_result {
+        Ok(r) => match get_reply(db, r.last_insert_id).await? {
+            Some(model) => Ok(model),
+            None => Err(anyhow!("Reply was not found after adding it to database")),
+        },
+        Err(e) => Err(anyhow!("Error adding reply to database: {:?}", e)),
+    }
+}
+
+// Replies are keyed by the member's server hash here so every thread counts towards the limit.
+pub async fn count_author_pending_replies(
+    db: &DatabaseConnection,
+    guild_author_hash: &str,
+) -> Result<u64> {
+    let pending_count = confession_replies::Entity::find()
+        .filter(confession_replies::Column::GuildAuthorHash.eq(guild_author_hash))
+        .filter(confession_replies::Column::Status.eq(ConfessionStatus::Pending))
+        .count(db)
+        .await;
+    match pending_count {
+        Ok(count) => Ok(count),
+        Err(e) => Err(anyhow!("Error counting replies in database: {:?}", e)),
+    }
+}
+
+// When the member's replies since `since` were sent, oldest first.
+pub async fn get_author_reply_times_since(
+    db: &DatabaseConnection,
+    guild_author_hash: &str,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<chrono::DateTime<chrono::Utc>>> {
+    let replied = confession_replies::Entity::find()
+        .select_only()
+        .column(confession_replies::Column::CreatedAt)
+        .filter(confession_replies::Column::GuildAuthorHash.eq(guild_author_hash))
+        .filter(confession_replies::Column::CreatedAt.gte(since))
+        .order_by_asc(confession_replies::Column::CreatedAt)
+        .into_tuple::<chrono::DateTime<chrono::Utc>>()
+        .all(db)
+        .await;
+    match replied {
+        Ok(times) => Ok(times),
+        Err(e) => Err(anyhow!("Error getting replies from database: {:?}", e)),
+    }
+}
+
+pub async fn set_reply(
+    db: &DatabaseConnection,
+    reply: confession_replies::Model,
+) -> Result<confession_replies::Model> {
+    let this_reply = confession_replies::ActiveModel {
+        id: Set(reply.id),
+        confession_id: Set(reply.confession_id),
+        author_hash: Set(reply.author_hash),
+        guild_author_hash: Set(reply.guild_author_hash),
+        pseudonym: Set(reply.pseudonym),
+        content: Set(reply.content),
+        status: Set(reply.status),
+        vetting_message_id: Set(reply.vetting_message_id),
+        posted_message_id: Set(reply.posted_message_id),
+        created_at: Set(reply.created_at),
+    };
+    match confession_replies::Entity::update(this_reply)
+        .exec(db)
+        .await
+    {
+        Ok(r) => Ok(r),
+        Err(e) => Err(anyhow!("Error setting reply in database: {:?}", e)),
+    }
+}
+
+// Moves a pending reply to `status` in a single statement so only one moderator can act on it.
+pub async fn resolve_reply(
+    db: &DatabaseConnection,
+    reply_id: i32,
+    status: ConfessionStatus,
+) -> Result<bool> {
+    let resolve_result = confession_replies::Entity::update_many()
+        .col_expr(confession_replies::Column::Status, Expr::value(status))
+        .filter(confession_replies::Column::Id.eq(reply_id))
+        .filter(confession_replies::Column::Status.eq(ConfessionStatus::Pending))
+        .exec(db)
+        .await;
+    match resolve_result {
+        Ok(r) => Ok(r.rows_affected > 0),
+        Err(e) => Err(anyhow!("Error resolving reply in database: {:?}", e)),
+    }
+}
+
+pub async fn get_reply_pseudonyms(
+    db: &DatabaseConnection,
+    confession_id: i32,
+) -> Result<Vec<reply_pseudonyms::Model>> {
+    let found_pseudonyms = reply_pseudonyms::Entity::find()
+        .filter(reply_pseudonyms::Column::ConfessionId.eq(confession_id))
+        .all(db)
+        .await;
+    match found_pseudonyms {
+        Ok(pseudonyms) => Ok(pseudonyms),
+        Err(e) => Err(anyhow!("Error getting pseudonyms from database: {:?}", e)),
+    }
+}
+
+// The unique indexes on the table reject a label (or replier) claimed concurrently.
+pub async fn add_reply_pseudonym(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    author_hash: String,
+    pseudonym: String,
+) -> Result<()> {
+    let this_pseudonym = reply_pseudonyms::ActiveModel {
+        confession_id: Set(confession_id),
+        author_hash: Set(author_hash),
+        pseudonym: Set(pseudonym),
+        ..Default::default()
+    };
+    match reply_pseudonyms::Entity::insert(this_pseudonym)
+        .exec(db)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error adding pseudonym to database: {:?}", e)),
+    }
+}
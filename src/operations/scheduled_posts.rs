@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::entity::scheduled_posts;
+
+pub async fn add_scheduled_post(
+    db: &DatabaseConnection,
+    guild_id: u64,
+    confession_id: i32,
+    post_at: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    let this_post = scheduled_posts::ActiveModel {
+        guild_id: Set(guild_id),
+        confession_id: Set(confession_id),
+        post_at: Set(post_at),
+        ..Default::default()
+    };
+    let add_result = scheduled_posts::Entity::insert(this_post).exec(db).await;
+    match add_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error adding scheduled post to database: {:?}", e)),
+    }
+}
+
+pub async fn get_due_scheduled_posts(
+    db: &DatabaseConnection,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<scheduled_posts::Model>> {
+    let found_posts = scheduled_posts::Entity::find()
+        .filter(scheduled_posts::Column::PostAt.lte(now))
+        .order_by_asc(scheduled_posts::Column::PostAt)
+        .all(db)
+        .await;
+    match found_posts {
+        Ok(posts) => Ok(posts),
+        Err(e) => Err(anyhow!(
+            "Error getting scheduled posts from database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn count_guild_scheduled_posts(db: &DatabaseConnection, guild_id: u64) -> Result<u64> {
+    let counted_posts = scheduled_posts::Entity::find()
+        .filter(scheduled_posts::Column::GuildId.eq(guild_id))
+        .count(db)
+        .await;
+    match counted_posts {
+        Ok(count) => Ok(count),
+        Err(e) => Err(anyhow!(
+            "Error counting scheduled posts in database: {:?}",
+            e
+        )),
+    }
+}
+
+pub async fn remove_scheduled_post(db: &DatabaseConnection, scheduled_post_id: i32) -> Result<()> {
+    let remove_result = scheduled_posts::Entity::delete_by_id(scheduled_post_id)
+        .exec(db)
+        .await;
+    match remove_result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!(
+            "Error removing scheduled post from database: {:?}",
+            e
+        )),
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{anyhow, Result};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+
+use crate::entity::confession_votes;
+
+pub async fn get_votes(
+    db: &DatabaseConnection,
+    confession_id: i32,
+) -> Result<Vec<confession_votes::Model>> {
+    let found_votes = confession_votes::Entity::find()
+        .filter(confession_votes::Column::ConfessionId.eq(confession_id))
+        .all(db)
+        .await;
+    match found_votes {
+        Ok(votes) => Ok(votes),
+        Err(e) => Err(anyhow!("Error getting votes from database: {:?}", e)),
+    }
+}
+
+// A moderator voting again replaces their earlier vote.
+pub async fn set_vote(
+    db: &DatabaseConnection,
+    confession_id: i32,
+    moderator_id: u64,
+    approve: bool,
+) -> Result<()> {
+    let existing = confession_votes::Entity::find()
+        .filter(confession_votes::Column::ConfessionId.eq(confession_id))
+        .filter(confession_votes::Column::ModeratorId.eq(moderator_id))
+        .one(db)
+        .await;
+    let result = match existing {
+        Ok(Some(vote)) => confession_votes::Entity::update(confession_votes::ActiveModel {
+            id: Set(vote.id),
+            confession_id: Set(confession_id),
+            moderator_id: Set(moderator_id),
+            approve: Set(approve as i8),
+        })
+        .exec(db)
+        .await
+        .map(|_| ()),
+        Ok(None) => confession_votes::Entity::insert(confession_votes::ActiveModel {
+            confession_id: Set(confession_id),
+            moderator_id: Set(moderator_id),
+            approve: Set(approve as i8),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .map(|_| ()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Error setting vote in database: {:?}", e)),
+    }
+}
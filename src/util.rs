@@ -1 +1,34 @@
+use std::{sync::Arc, time::Duration};
 
+use poise::{serenity_prelude as serenity, Modal};
+
+/// Like `poise::execute_modal`, but opened from a button press instead of a command.
+///
+/// The submitted interaction is returned unanswered so the caller can respond to it.
+pub async fn execute_component_modal<M: Modal>(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    defaults: Option<M>,
+    timeout: Option<Duration>,
+) -> Result<Option<(Arc<serenity::ModalSubmitInteraction>, M)>, serenity::Error> {
+    let interaction_id = component.id.to_string();
+
+    component
+        .create_interaction_response(ctx, |b| {
+            *b = M::create(defaults, interaction_id.clone());
+            b
+        })
+        .await?;
+
+    let response = serenity::CollectModalInteraction::new(&ctx.shard)
+        .filter(move |d| d.data.custom_id == interaction_id)
+        .timeout(timeout.unwrap_or(Duration::from_secs(3600)))
+        .await;
+    let response = match response {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+
+    let parsed = M::parse(response.data.clone()).map_err(serenity::Error::Other)?;
+    Ok(Some((response, parsed)))
+}